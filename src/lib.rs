@@ -56,10 +56,20 @@ extern crate doc_comment;
 #[cfg(test)]
 doctest!("../README.md");
 
+mod targets;
+
+use std::borrow::Borrow;
+use std::cmp::Ordering;
 use std::collections::HashMap;
-use std::process::Command;
-use std::{env, error, fmt, io, num, str};
-use std::{ffi::OsString, str::FromStr};
+use std::ffi::{OsStr, OsString};
+use std::hash::{Hash, Hasher};
+use std::io::Write;
+use std::path::Path;
+use std::process::{self, Command, Stdio};
+use std::slice;
+use std::str::FromStr;
+use std::sync::atomic::{self, AtomicUsize};
+use std::{env, error, fmt, fs, io, num, str};
 
 // Convenience re-export to allow version comparison without needing to add
 // semver crate.
@@ -70,164 +80,1906 @@ use Error::*;
 /// Release channel of the compiler.
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 pub enum Channel {
+    // variants must be ordered from least to most stable for comparison to be correct
     /// Development release channel
     Dev,
     /// Nightly release channel
     Nightly,
     /// Beta release channel
+    ///
+    /// Release candidates (an `rc` pre-release tag, as produced by some
+    /// custom builds) are reported as beta, since that is the channel they
+    /// are cut from.
     Beta,
     /// Stable release channel
     Stable,
 }
 
-/// LLVM version
+impl Channel {
+    /// Returns all channels, ordered from least to most stable.
+    pub const fn variants() -> [Channel; 4] {
+        [
+            Channel::Dev,
+            Channel::Nightly,
+            Channel::Beta,
+            Channel::Stable,
+        ]
+    }
+
+    /// Returns the name of the channel: `dev`, `nightly`, `beta` or `stable`.
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            Channel::Dev => "dev",
+            Channel::Nightly => "nightly",
+            Channel::Beta => "beta",
+            Channel::Stable => "stable",
+        }
+    }
+}
+
+/// The default channel is `Stable`, the channel most users are on.
+impl Default for Channel {
+    fn default() -> Channel {
+        Channel::Stable
+    }
+}
+
+impl PartialEq<str> for Channel {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl<'a> PartialEq<&'a str> for Channel {
+    fn eq(&self, other: &&'a str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+/// Returns the channel of a rustup toolchain name, such as
+/// `stable-x86_64-unknown-linux-gnu` or `nightly-2023-06-01`.
+///
+/// Toolchains named by a version number, like `1.70.0` or `1.70`, are stable.
+/// Returns `None` for custom toolchains.
+pub fn channel_from_toolchain_name(name: &str) -> Option<Channel> {
+    let channel = name.split('-').next().unwrap_or("");
+    match channel {
+        "stable" => Some(Channel::Stable),
+        "beta" => Some(Channel::Beta),
+        "nightly" => Some(Channel::Nightly),
+        _ if parse_partial_version(channel).is_ok() => Some(Channel::Stable),
+        _ => None,
+    }
+}
+
+/// Returns the rustup toolchain that is active for this process, as set in
+/// `$RUSTUP_TOOLCHAIN` by rustup when it runs cargo or rustc.
+pub fn active_rustup_toolchain() -> Option<String> {
+    env::var("RUSTUP_TOOLCHAIN").ok().filter(|s| !s.is_empty())
+}
+
+/// Returns the channel of the active rustup toolchain, without running rustc.
+///
+/// See `active_rustup_toolchain` and `channel_from_toolchain_name`.
+pub fn channel_from_rustup_env() -> Option<Channel> {
+    channel_from_toolchain_name(&active_rustup_toolchain()?)
+}
+
+/// Returns whether `$RUSTC_BOOTSTRAP` allows unstable features on any
+/// channel, either for all crates (`1`) or for a list of crate names.
+///
+/// Returns `false` if it is unset, empty, `0`, or `-1` (which makes nightly
+/// compilers behave like stable ones).
+pub fn rustc_bootstrap_enabled() -> bool {
+    match env::var("RUSTC_BOOTSTRAP") {
+        Ok(value) => {
+            let value = value.trim();
+            !value.is_empty() && value != "0" && value != "-1"
+        }
+        Err(_) => false,
+    }
+}
+
+/// Guesses the channel of a compiler from the file name of its program, such
+/// as `rustc-nightly` or `/usr/bin/rustc-beta.exe`.
+///
+/// This is a best-effort hint that can be checked before running the compiler;
+/// the suffix after the first `-` is interpreted like
+/// `channel_from_toolchain_name`. Returns `None` for names without a channel,
+/// such as plain `rustc`.
+pub fn channel_hint_from_program(program: &OsStr) -> Option<Channel> {
+    let name = Path::new(program).file_name()?.to_str()?;
+    let name = if name.ends_with(".exe") {
+        &name[..name.len() - ".exe".len()]
+    } else {
+        name
+    };
+    let suffix = name.splitn(2, '-').nth(1)?;
+    channel_from_toolchain_name(suffix)
+}
+
+/// The tool that reported a `VersionMeta`.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub enum CompilerKind {
+    /// `rustc`
+    Rustc,
+    /// `cargo`
+    Cargo,
+    /// `rustdoc`
+    Rustdoc,
+    /// Miri, reporting the version of the `rustc` it is built on
+    Miri,
+}
+
+impl CompilerKind {
+    // the program named in the version output
+    fn program(self) -> &'static str {
+        match self {
+            CompilerKind::Rustc | CompilerKind::Miri => "rustc",
+            CompilerKind::Cargo => "cargo",
+            CompilerKind::Rustdoc => "rustdoc",
+        }
+    }
+}
+
+/// LLVM version
+///
+/// LLVM's version numbering scheme is not semver compatible until version 4.0
+///
+/// Some versions of rustc [just print the major and minor versions], in which case
+/// the patch version is `None`.
+///
+/// [just print the major and minor versions]: https://github.com/rust-lang/rust/blob/b5c9e2448c9ace53ad5c11585803894651b18b0a/compiler/rustc_codegen_llvm/src/llvm_util.rs#L173-L178
+#[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct LlvmVersion {
+    // fields must be ordered major, minor, patch for comparison to be correct
+    /// Major version
+    pub major: u64,
+    /// Minor version
+    pub minor: u64,
+    /// Patch version, if reported; a missing patch version sorts before any
+    /// patch version of the same `major.minor`
+    pub patch: Option<u64>,
+}
+
+impl LlvmVersion {
+    /// Creates a version from a major version only, as used since LLVM 4.0.
+    pub fn from_major(major: u64) -> LlvmVersion {
+        LlvmVersion {
+            major,
+            minor: 0,
+            patch: None,
+        }
+    }
+
+    /// Formats the version without a `.0` minor version, e.g. `11` for 11.0.
+    ///
+    /// Versions with a nonzero minor version, which are only meaningful before
+    /// LLVM 4.0, are formatted in full.
+    pub fn to_major_string(&self) -> String {
+        if self.minor == 0 {
+            self.major.to_string()
+        } else {
+            self.to_string()
+        }
+    }
+
+    /// Parses `s` like `from_str`, but first strips a `-rust` suffix, as in
+    /// `17.0.6-rust-1.80.0-stable`.
+    ///
+    /// This is how the LLVM bundled with rustc identifies itself outside of
+    /// `rustc -vV`.
+    pub fn parse_lenient(s: &str) -> Result<LlvmVersion, LlvmVersionParseError> {
+        match s.find("-rust") {
+            Some(i) => s[..i].parse(),
+            None => s.parse(),
+        }
+    }
+
+    /// Encodes the version as `major * 1_000_000 + minor * 1_000 + patch`,
+    /// e.g. `17_000_006` for 17.0.6, with a missing patch version as `0`.
+    ///
+    /// Returns `None` if the minor or patch version is 1000 or more, which
+    /// would collide with other versions, or if the result overflows.
+    pub fn to_u64(&self) -> Option<u64> {
+        let patch = self.patch.unwrap_or(0);
+        if self.minor >= 1_000 || patch >= 1_000 {
+            return None;
+        }
+        self.major
+            .checked_mul(1_000_000)?
+            .checked_add(self.minor * 1_000 + patch)
+    }
+
+    /// Decodes a version encoded by `to_u64`. The patch version is always
+    /// `Some`.
+    pub fn from_u64(n: u64) -> LlvmVersion {
+        LlvmVersion {
+            major: n / 1_000_000,
+            minor: n / 1_000 % 1_000,
+            patch: Some(n % 1_000),
+        }
+    }
+
+    /// Returns the major, minor and patch versions.
+    pub fn major_minor_patch(&self) -> (u64, u64, Option<u64>) {
+        (self.major, self.minor, self.patch)
+    }
+
+    /// Returns whether this version is `major.minor` or later.
+    pub fn is_at_least(&self, major: u64, minor: u64) -> bool {
+        (self.major, self.minor) >= (major, minor)
+    }
+
+    /// Returns whether this version is earlier than `major.minor`.
+    pub fn is_before(&self, major: u64, minor: u64) -> bool {
+        !self.is_at_least(major, minor)
+    }
+}
+
+impl fmt::Display for LlvmVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)?;
+        if let Some(patch) = self.patch {
+            write!(f, ".{}", patch)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for LlvmVersion {
+    type Err = LlvmVersionParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s
+            .split('.')
+            .map(|part| -> Result<u64, LlvmVersionParseError> {
+                if part == "0" {
+                    Ok(0)
+                } else if part.starts_with('0') {
+                    Err(LlvmVersionParseError::ComponentMustNotHaveLeadingZeros(
+                        part.to_owned(),
+                    ))
+                } else if part.starts_with('-') || part.starts_with('+') {
+                    Err(LlvmVersionParseError::ComponentMustNotHaveSign(
+                        part.to_owned(),
+                    ))
+                } else {
+                    Ok(part.parse()?)
+                }
+            });
+
+        let major = parts.next().unwrap()?;
+        let mut minor = 0;
+
+        if let Some(part) = parts.next() {
+            minor = part?;
+        } else if major < 4 {
+            // LLVM versions earlier than 4.0 have significant minor versions, so require the minor version in this case.
+            return Err(LlvmVersionParseError::MinorVersionRequiredBefore4(
+                s.to_owned(),
+            ));
+        }
+
+        let patch = match parts.next() {
+            Some(part) => Some(part?),
+            None => None,
+        };
+
+        if parts.next().is_some() {
+            return Err(LlvmVersionParseError::TooManyComponents(s.to_owned()));
+        }
+
+        Ok(Self {
+            major,
+            minor,
+            patch,
+        })
+    }
+}
+
+/// Rustc version plus metadata like git short hash and build date.
+///
+/// Besides the public fields, this holds further details of the parsed
+/// output, such as which tool printed it. It therefore can't be built with a
+/// struct literal; use `VersionMeta::new` instead.
+#[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct VersionMeta {
+    /// Version of the compiler
+    pub semver: Version,
+
+    /// Git short hash of the build of the compiler
+    pub commit_hash: Option<String>,
+
+    /// Commit date of the compiler
+    pub commit_date: Option<String>,
+
+    /// Build date of the compiler; this was removed between Rust 1.0.0 and 1.1.0.
+    ///
+    /// It is also read from the `rust-birthday` field of older compilers.
+    pub build_date: Option<String>,
+
+    /// Release channel of the compiler
+    pub channel: Channel,
+
+    /// Host target triple of the compiler
+    pub host: String,
+
+    /// Short version string of the compiler
+    pub short_version_string: String,
+
+    /// Version of LLVM used by the compiler
+    pub llvm_version: Option<LlvmVersion>,
+
+    // all reported LLVM versions, which only apply while `llvm_version` is
+    // still the first of them; like the precision, these are details of the
+    // output that don't affect comparisons
+    llvm_versions: NotCompared<Vec<LlvmVersion>>,
+
+    // the verbatim output this was parsed from; formatting differences don't
+    // affect comparisons
+    raw: NotCompared<Option<String>>,
+
+    // number of components the first LLVM version was reported with
+    llvm_version_precision: NotCompared<Option<u8>>,
+
+    // the tool whose output this was parsed from; Miri reports the underlying
+    // rustc, so this doesn't affect comparisons either
+    kind: NotCompared<CompilerKind>,
+
+    // whether the compiler was built with debug assertions, if reported
+    debug_assertions: Option<bool>,
+
+    // whether the compiler was built with the parallel front-end, if reported
+    parallel_frontend: Option<bool>,
+}
+
+impl VersionMeta {
+    /// Returns the metadata of a compiler with the given version, channel,
+    /// host and short version string, like `rustc 1.70.0 (90c541806 2023-05-31)`.
+    ///
+    /// The optional fields are `None`; set them directly or with the `with_*`
+    /// methods. The kind of tool is derived from the short version string.
+    pub fn new<H, S>(semver: Version, channel: Channel, host: H, short_version_string: S) -> Self
+    where
+        H: Into<String>,
+        S: Into<String>,
+    {
+        let short_version_string = short_version_string.into();
+        let kind = match short_version_string.split(' ').next() {
+            Some("cargo") => CompilerKind::Cargo,
+            Some("rustdoc") => CompilerKind::Rustdoc,
+            _ => CompilerKind::Rustc,
+        };
+        VersionMeta {
+            semver,
+            commit_hash: None,
+            commit_date: None,
+            build_date: None,
+            channel,
+            host: host.into(),
+            short_version_string,
+            llvm_version: None,
+            llvm_versions: NotCompared(Vec::new()),
+            raw: NotCompared(None),
+            llvm_version_precision: NotCompared(None),
+            kind: NotCompared(kind),
+            debug_assertions: None,
+            parallel_frontend: None,
+        }
+    }
+
+    /// Returns the version metadata for `cmd`, which should be a `rustc` command.
+    pub fn for_command(cmd: Command) -> Result<VersionMeta> {
+        let mut cmd = DescribedCommand::from_caller(cmd);
+        cmd.arg("-vV");
+        version_meta_from_command(cmd)
+    }
+
+    /// Returns the version metadata for `cmd`, which should print the output
+    /// of `rustc -vV`.
+    ///
+    /// Unlike `for_command`, this doesn't add `-vV` to the arguments, for
+    /// commands that already include it or print the output by other means.
+    ///
+    /// If the output lacks the expected fields, `Error::UnexpectedOutput` is
+    /// returned with the start of the output.
+    pub fn for_command_raw(cmd: Command) -> Result<VersionMeta> {
+        version_meta_from_command(DescribedCommand::from_caller(cmd))
+    }
+
+    /// Like `for_command`, but passes the long spelling `--version --verbose`
+    /// instead of `-vV`, for wrappers that don't understand combined flags.
+    pub fn for_command_long_flags(cmd: Command) -> Result<VersionMeta> {
+        let mut cmd = DescribedCommand::from_caller(cmd);
+        cmd.arg("--version").arg("--verbose");
+        version_meta_from_command(cmd)
+    }
+
+    /// Returns the version metadata for the `rustc` command built by `make_cmd`.
+    ///
+    /// `Command` is not `Clone` and [`for_command`](Self::for_command) consumes
+    /// it, so a configured command can't be reused across queries directly.
+    /// Instead, pass a closure that builds a fresh command (with any arguments,
+    /// environment or working directory) each time; the same closure can then
+    /// be passed by `&mut` reference to repeated calls.
+    pub fn for_command_with<F>(make_cmd: F) -> Result<VersionMeta>
+    where
+        F: FnOnce() -> Command,
+    {
+        VersionMeta::for_command(make_cmd())
+    }
+
+    /// Returns whether this looks like an official release of the compiler.
+    ///
+    /// This is a heuristic: only stable and beta compilers that report a
+    /// commit hash are considered official. Nightlies, dev builds (which
+    /// includes locally built and CI try builds) and builds without commit
+    /// information, such as many distribution packages, are not.
+    pub fn is_official_release(&self) -> bool {
+        match self.channel {
+            Channel::Stable | Channel::Beta => self.commit_hash.is_some(),
+            Channel::Nightly | Channel::Dev => false,
+        }
+    }
+
+    /// Returns whether this looks like a distribution's build of a stable
+    /// compiler.
+    ///
+    /// Distribution packages (e.g. Debian's) typically report `unknown` commit
+    /// information but do report the LLVM version they were built against.
+    pub fn is_distro_build(&self) -> bool {
+        self.channel == Channel::Stable
+            && self.commit_hash.is_none()
+            && self.commit_date.is_none()
+            && self.llvm_version.is_some()
+    }
+
+    /// Compares the compiler version against `other`.
+    ///
+    /// SemVer orders a pre-release such as `1.50.0-nightly` before `1.50.0`,
+    /// yet a nightly usually already has the features that `1.50.0` will
+    /// stabilize. If `nightly_as_release` is set, a nightly compiler is
+    /// compared by its major, minor and patch version only, so the nightly of
+    /// a release compares `Equal` to it. Otherwise this is plain SemVer
+    /// ordering.
+    pub fn compare_to(&self, other: &Version, nightly_as_release: bool) -> Ordering {
+        if nightly_as_release && self.channel == Channel::Nightly {
+            let v = &self.semver;
+            (v.major, v.minor, v.patch).cmp(&(other.major, other.minor, other.patch))
+        } else {
+            self.semver.cmp(other)
+        }
+    }
+
+    /// Returns the LLVM version, or `default` if the compiler didn't report one.
+    ///
+    /// rustc only prints its LLVM version since 1.16.0.
+    pub fn llvm_version_or(&self, default: LlvmVersion) -> LlvmVersion {
+        self.llvm_version.clone().unwrap_or(default)
+    }
+
+    /// Returns whether `host` is a target triple known to rustc.
+    ///
+    /// The host is checked against a list of targets embedded in this crate,
+    /// taken from a recent rustc's `--print target-list`. It is updated along
+    /// with new Rust releases, so triples of targets added after the last
+    /// update, as well as custom target triples, are not recognized.
+    pub fn host_is_known_target(&self) -> bool {
+        targets::KNOWN_TARGETS
+            .binary_search(&self.host.as_str())
+            .is_ok()
+    }
+
+    /// Returns `N` for a `beta.N` pre-release, such as `1.70.0-beta.3`.
+    ///
+    /// This is `None` for other channels, and for betas that don't carry a
+    /// number (like `1.5.0-beta`).
+    pub fn beta_number(&self) -> Option<u64> {
+        let mut parts = self.semver.pre.split('.');
+        if parts.next() != Some("beta") {
+            return None;
+        }
+
+        parts.next().and_then(|n| n.parse().ok())
+    }
+
+    /// Returns an error if the compiler version is older than `min`.
+    pub fn require_min(&self, min: Version) -> Result<()> {
+        if self.semver < min {
+            return Err(Error::VersionTooOld {
+                found: self.semver.clone(),
+                required: min,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Returns whether the compiler is version `major.minor` or later,
+    /// regardless of its patch version.
+    ///
+    /// Features are stabilized in `x.y.0` releases, so this is usually what a
+    /// feature check wants. Pre-release tags are ignored as well, so a nightly
+    /// of `major.minor.0` satisfies it too.
+    pub fn at_least_minor(&self, major: u64, minor: u64) -> bool {
+        (self.semver.major, self.semver.minor) >= (major, minor)
+    }
+
+    /// Returns whether this looks like a compiler built locally from the
+    /// rust-lang/rust repository, e.g. with `./x.py build`.
+    ///
+    /// `rustc -vV` doesn't report the bootstrap stage, so this is a best-effort
+    /// heuristic: such compilers are on the dev channel and usually lack
+    /// commit information.
+    pub fn is_bootstrap_build(&self) -> bool {
+        self.channel == Channel::Dev && self.commit_hash.is_none() && self.commit_date.is_none()
+    }
+
+    /// Returns the commit date of a nightly compiler, which is the date of the
+    /// nightly, or `None` for other channels.
+    pub fn nightly_date(&self) -> Option<&str> {
+        match self.channel {
+            Channel::Nightly => self.commit_date.as_ref().map(|d| d.as_str()),
+            Channel::Dev | Channel::Beta | Channel::Stable => None,
+        }
+    }
+
+    /// Returns whether the compiler's LLVM is recent enough for `target`.
+    ///
+    /// This consults a small table of targets whose LLVM backend only became
+    /// usable in a known LLVM major version. Returns `None` if `target` isn't
+    /// in the table or the compiler didn't report its LLVM version.
+    pub fn llvm_supports_target(&self, target: &str) -> Option<bool> {
+        let llvm = self.llvm_version.as_ref()?;
+        LLVM_TARGET_MINIMUMS
+            .iter()
+            .find(|&&(t, _)| t == target)
+            .map(|&(_, major)| llvm.major >= major)
+    }
+
+    /// Returns all LLVM versions reported by the compiler, starting with
+    /// `llvm_version`.
+    ///
+    /// Some builds report both the bundled and the system LLVM version, on
+    /// separate lines. If `llvm_version` was changed after parsing, only that
+    /// version is returned.
+    pub fn all_llvm_versions(&self) -> &[LlvmVersion] {
+        if let Some(versions) = self.parsed_llvm_versions() {
+            return versions;
+        }
+        match self.llvm_version {
+            Some(ref llvm_version) => slice::from_ref(llvm_version),
+            None => &[],
+        }
+    }
+
+    // the parsed LLVM versions, if `llvm_version` wasn't changed since
+    fn parsed_llvm_versions(&self) -> Option<&[LlvmVersion]> {
+        let versions = &self.llvm_versions.0;
+        if versions.first() == self.llvm_version.as_ref() {
+            Some(versions)
+        } else {
+            None
+        }
+    }
+
+    /// Returns whether the compiler's channel is at least as stable as `min`,
+    /// where dev < nightly < beta < stable.
+    pub fn channel_stability_at_least(&self, min: Channel) -> bool {
+        self.channel >= min
+    }
+
+    /// Returns how many components the LLVM version was reported with, e.g.
+    /// 1 for `11`, 2 for `11.0` and 3 for `11.0.1`.
+    ///
+    /// Returns `None` if the compiler didn't report its LLVM version. If
+    /// `llvm_version` was changed after parsing, this is the number of
+    /// components of its `Display` form.
+    pub fn llvm_version_precision(&self) -> Option<u8> {
+        let llvm_version = self.llvm_version.as_ref()?;
+        match self.llvm_version_precision.0 {
+            Some(precision) if self.parsed_llvm_versions().is_some() => Some(precision),
+            _ if llvm_version.patch.is_some() => Some(3),
+            _ => Some(2),
+        }
+    }
+
+    /// Returns whether the compiler's host is Windows.
+    pub fn host_is_windows(&self) -> bool {
+        self.host.split('-').any(|c| c == "windows")
+    }
+
+    /// Returns whether the compiler's host is an Apple platform, such as macOS.
+    pub fn host_is_apple(&self) -> bool {
+        self.host.split('-').any(|c| c == "apple")
+    }
+
+    /// Returns whether the compiler's host uses the musl C library.
+    pub fn host_is_musl(&self) -> bool {
+        self.host.split('-').any(|c| c.starts_with("musl"))
+    }
+
+    /// Returns whether the compiler's host uses the MSVC toolchain.
+    pub fn host_is_msvc(&self) -> bool {
+        self.host.split('-').any(|c| c == "msvc")
+    }
+
+    /// Returns whether the compiler's host is Android.
+    pub fn host_is_android(&self) -> bool {
+        self.host.split('-').any(|c| c.starts_with("android"))
+    }
+
+    /// Returns whether `self` and `other` are equal apart from their LLVM
+    /// versions, e.g. for distributions that build the same rustc against
+    /// different LLVM versions.
+    pub fn eq_ignoring_llvm(&self, other: &VersionMeta) -> bool {
+        self.clone().with_llvm_version(None) == other.clone().with_llvm_version(None)
+    }
+
+    /// Returns the verbatim output this was parsed from, e.g. for attaching to
+    /// bug reports.
+    ///
+    /// This is `None` for metadata read with `from_json`. The output isn't
+    /// taken into account when comparing or hashing metas, so the same output
+    /// with and without e.g. a trailing newline parses to equal metas.
+    pub fn raw(&self) -> Option<&str> {
+        self.raw.0.as_ref().map(|s| &**s)
+    }
+
+    /// Returns which tool reported this version, as determined by the program
+    /// name at the start of the output.
+    pub fn kind(&self) -> CompilerKind {
+        self.kind.0
+    }
+
+    /// Returns whether the compiler version is at least `min`, where `min` may
+    /// omit the patch version, as in `"1.56"`.
+    ///
+    /// A missing patch version is treated as `.0`. Versions are compared with
+    /// SemVer ordering, so e.g. `1.56.0-nightly` is not at least `"1.56"`.
+    pub fn semver_at_least(&self, min: &str) -> Result<bool> {
+        Ok(self.semver >= parse_partial_version(min)?)
+    }
+
+    /// Returns whether the feature stabilized in version `min` is available.
+    ///
+    /// This is the case for stable and beta compilers of at least `min`, where
+    /// the pre-release tag of a beta is ignored since a beta already contains
+    /// everything its release will stabilize. Nightly and dev compilers are
+    /// always accepted, assuming the feature can be enabled there, either
+    /// because it's already stable or through its `#![feature]` gate.
+    pub fn has_stable_feature(&self, min: Version) -> bool {
+        match self.channel {
+            Channel::Nightly | Channel::Dev => true,
+            Channel::Beta | Channel::Stable => {
+                (self.semver.major, self.semver.minor, self.semver.patch)
+                    >= (min.major, min.minor, min.patch)
+            }
+        }
+    }
+
+    /// Formats this version in the format of `rustc -vV`.
+    ///
+    /// Parsing the result with `version_meta_for` yields an equal
+    /// `VersionMeta`. The version line of Miri isn't stored and so is not
+    /// reproduced: the output describes the underlying `rustc`, and `kind`
+    /// returns `CompilerKind::Rustc` for the parsed result. The kind doesn't
+    /// take part in comparisons.
+    pub fn to_verbose_string(&self) -> String {
+        let mut out = format!(
+            "{}\nbinary: {}\n",
+            self.short_version_string,
+            self.kind.0.program()
+        );
+        out += &format!(
+            "commit-hash: {}\n",
+            self.commit_hash.as_ref().map_or("unknown", |s| &**s)
+        );
+        out += &format!(
+            "commit-date: {}\n",
+            self.commit_date.as_ref().map_or("unknown", |s| &**s)
+        );
+        if let Some(ref build_date) = self.build_date {
+            out += &format!("build-date: {}\n", build_date);
+        }
+        out += &format!("host: {}\nrelease: {}\n", self.host, self.semver);
+        let flag = |value| if value { "yes" } else { "no" };
+        if let Some(debug_assertions) = self.debug_assertions {
+            out += &format!("debug-assertions: {}\n", flag(debug_assertions));
+        }
+        if let Some(parallel_frontend) = self.parallel_frontend {
+            out += &format!("parallel: {}\n", flag(parallel_frontend));
+        }
+        for (i, llvm_version) in self.all_llvm_versions().iter().enumerate() {
+            if i == 0 && self.llvm_version_precision() == Some(1) {
+                out += &format!("LLVM version: {}\n", llvm_version.major);
+            } else {
+                out += &format!("LLVM version: {}\n", llvm_version);
+            }
+        }
+        out
+    }
+
+    /// Returns a link to the commit the compiler was built from on GitHub, if
+    /// the commit hash is known.
+    pub fn commit_url(&self) -> Option<String> {
+        self.commit_hash
+            .as_ref()
+            .map(|hash| format!("https://github.com/rust-lang/rust/commit/{}", hash))
+    }
+
+    /// Describes each field that differs between `self` and `other`, e.g.
+    /// `semver: 1.69.0 -> 1.70.0`.
+    ///
+    /// Missing values are shown as `unknown`, like in `rustc -vV` output.
+    /// Returns an empty `Vec` if the public fields are equal.
+    pub fn diff(&self, other: &VersionMeta) -> Vec<String> {
+        fn opt<T: ToString>(value: &Option<T>) -> String {
+            value
+                .as_ref()
+                .map_or_else(|| "unknown".to_owned(), |v| v.to_string())
+        }
+
+        let fields = [
+            ("semver", self.semver.to_string(), other.semver.to_string()),
+            (
+                "commit-hash",
+                opt(&self.commit_hash),
+                opt(&other.commit_hash),
+            ),
+            (
+                "commit-date",
+                opt(&self.commit_date),
+                opt(&other.commit_date),
+            ),
+            ("build-date", opt(&self.build_date), opt(&other.build_date)),
+            (
+                "channel",
+                self.channel.as_str().to_owned(),
+                other.channel.as_str().to_owned(),
+            ),
+            ("host", self.host.clone(), other.host.clone()),
+            (
+                "short",
+                self.short_version_string.clone(),
+                other.short_version_string.clone(),
+            ),
+            ("llvm", opt(&self.llvm_version), opt(&other.llvm_version)),
+        ];
+
+        fields
+            .iter()
+            .filter(|(_, a, b)| a != b)
+            .map(|(name, a, b)| format!("{}: {} -> {}", name, a, b))
+            .collect()
+    }
+
+    /// Returns the pointer width of the compiler's host in bits, derived from
+    /// the architecture in the host triple.
+    ///
+    /// Accounts for 64-bit architectures with 32-bit pointers, such as
+    /// `x86_64-unknown-linux-gnux32`. Returns `None` for unknown architectures.
+    pub fn host_pointer_width(&self) -> Option<u8> {
+        let mut components = self.host.split('-');
+        let arch = components.next()?;
+        if components.any(|c| c.ends_with("x32") || c.ends_with("_ilp32")) {
+            return Some(32);
+        }
+        ARCH_POINTER_WIDTHS
+            .iter()
+            .find(|&&(prefix, _)| arch.starts_with(prefix))
+            .map(|&(_, width)| width)
+    }
+
+    /// Formats the public fields as a JSON object, e.g. for `--version --json`
+    /// output, without depending on serde.
+    ///
+    /// Missing values are `null`; the channel and LLVM version are strings.
+    pub fn to_json(&self) -> String {
+        fn opt(value: Option<String>) -> String {
+            value.map_or_else(|| "null".to_owned(), |v| json_string(&v))
+        }
+
+        format!(
+            "{{\"semver\":{},\"commit_hash\":{},\"commit_date\":{},\"build_date\":{},\
+             \"channel\":{},\"host\":{},\"short_version_string\":{},\"llvm_version\":{}}}",
+            json_string(&self.semver.to_string()),
+            opt(self.commit_hash.clone()),
+            opt(self.commit_date.clone()),
+            opt(self.build_date.clone()),
+            json_string(self.channel.as_str()),
+            json_string(&self.host),
+            json_string(&self.short_version_string),
+            opt(self.llvm_version.as_ref().map(|v| v.to_string())),
+        )
+    }
+
+    /// Parses the JSON produced by `to_json`.
+    ///
+    /// Only the public fields are stored in the JSON, so any additional LLVM
+    /// versions are lost, and the kind of tool is derived from the short
+    /// version string. Missing optional fields are treated as `null`.
+    pub fn from_json(s: &str) -> Result<VersionMeta> {
+        let map = parse_json_object(s)?;
+        let get = |key: &str| map.get(key).cloned().and_then(|v| v);
+        let required =
+            |key: &str| get(key).ok_or_else(|| InvalidJson(format!("missing `{}`", key)));
+
+        let channel = match &*required("channel")? {
+            "dev" => Channel::Dev,
+            "nightly" => Channel::Nightly,
+            "beta" => Channel::Beta,
+            "stable" => Channel::Stable,
+            x => return Err(InvalidJson(format!("unknown channel `{}`", x))),
+        };
+        let mut meta = VersionMeta::new(
+            required("semver")?.parse()?,
+            channel,
+            required("host")?,
+            required("short_version_string")?,
+        );
+        meta.commit_hash = get("commit_hash");
+        meta.commit_date = get("commit_date");
+        meta.build_date = get("build_date");
+        if let Some(v) = get("llvm_version") {
+            let llvm_version: LlvmVersion = v.parse()?;
+            meta.llvm_versions = NotCompared(vec![llvm_version.clone()]);
+            meta.llvm_version_precision = NotCompared(Some(v.split('.').count() as u8));
+            meta.llvm_version = Some(llvm_version);
+        }
+        Ok(meta)
+    }
+
+    /// Returns whether this is a pre-release compiler, i.e. not on the stable
+    /// channel.
+    pub fn is_prerelease(&self) -> bool {
+        self.channel != Channel::Stable
+    }
+
+    /// Returns the stable version this compiler's release will become, i.e.
+    /// `semver` without its pre-release tag, e.g. `1.80.0` for
+    /// `1.80.0-nightly`.
+    pub fn stable_equivalent(&self) -> Version {
+        Version::new(self.semver.major, self.semver.minor, self.semver.patch)
+    }
+
+    /// Evaluates each version requirement in `reqs`, such as `>=1.70`, against
+    /// `semver`, and returns whether each one is satisfied.
+    ///
+    /// SemVer rules apply, so e.g. `1.80.0-nightly` doesn't satisfy `>=1.70`.
+    /// Fails with `Error::ReqParseError` for the first requirement that can't
+    /// be parsed.
+    pub fn evaluate_reqs<'a, I>(&self, reqs: I) -> Result<Vec<(&'a str, bool)>>
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        reqs.into_iter()
+            .map(|req| match semver::VersionReq::parse(req) {
+                Ok(parsed) => Ok((req, parsed.matches(&self.semver))),
+                Err(source) => Err(ReqParseError {
+                    req: req.to_owned(),
+                    source,
+                }),
+            })
+            .collect()
+    }
+
+    /// Returns whether the compiler itself was built with debug assertions,
+    /// as reported by a `debug-assertions: yes` or `no` line.
+    ///
+    /// Official `rustc` builds don't report this, so this is `None` for them.
+    pub fn compiler_debug_assertions(&self) -> Option<bool> {
+        self.debug_assertions
+    }
+
+    /// Returns whether the compiler meets an MSRV policy of `major.minor`,
+    /// with any patch version.
+    ///
+    /// The pre-release tag is ignored, so a `1.70.0-nightly` satisfies an MSRV
+    /// of 1.70. This is the same check as `at_least_minor`.
+    pub fn satisfies_msrv(&self, major: u64, minor: u64) -> bool {
+        self.at_least_minor(major, minor)
+    }
+
+    /// Returns whether `other` is the same `major.minor.patch` release as this
+    /// compiler, but on a different channel, e.g. `1.80.0-nightly` and the
+    /// stable `1.80.0`.
+    pub fn same_release_different_channel(&self, other: &VersionMeta) -> bool {
+        self.stable_equivalent() == other.stable_equivalent() && self.channel != other.channel
+    }
+
+    /// Returns the latest stable edition supported by the compiler: `"2015"`,
+    /// `"2018"` (since 1.31), `"2021"` (since 1.56) or `"2024"` (since 1.85).
+    ///
+    /// This is also the edition `cargo new` of the same release uses. The
+    /// pre-release tag is ignored, so a nightly is treated like the release
+    /// it becomes.
+    pub fn default_edition(&self) -> &'static str {
+        if self.at_least_minor(1, 85) {
+            "2024"
+        } else if self.at_least_minor(1, 56) {
+            "2021"
+        } else if self.at_least_minor(1, 31) {
+            "2018"
+        } else {
+            "2015"
+        }
+    }
+
+    /// Returns a copy without the metadata that changes between builds of the
+    /// same compiler: the commit hash, commit date, build date and raw output
+    /// are cleared, and the short version string is reduced to e.g.
+    /// `rustc 1.80.0`.
+    ///
+    /// This allows comparing rebuilds of the same toolchain for equality.
+    pub fn without_metadata(&self) -> VersionMeta {
+        let mut meta = self.clone();
+        meta.commit_hash = None;
+        meta.commit_date = None;
+        meta.build_date = None;
+        meta.raw = NotCompared(None);
+        meta.short_version_string = format!("{} {}", self.kind.0.program(), self.semver);
+        meta
+    }
+
+    /// Returns whether `channel` matches the pre-release tag of `semver`, as it
+    /// does for parsed output.
+    ///
+    /// Stable compilers have no pre-release tag, beta compilers a `beta` or
+    /// `rc` tag, and nightly compilers a `nightly` tag. Dev compilers have
+    /// any other tag, since `version_meta_for_lossy` reports unknown tags as
+    /// dev. This catches inconsistent metas, e.g. built with `with_channel`.
+    pub fn channel_consistent_with_semver(&self) -> bool {
+        let pre = self.semver.pre.split('.').next().unwrap_or("");
+        match self.channel {
+            Channel::Stable => pre.is_empty(),
+            Channel::Beta => pre == "beta" || pre == "rc",
+            Channel::Nightly => pre == "nightly",
+            Channel::Dev => !pre.is_empty() && pre != "beta" && pre != "rc" && pre != "nightly",
+        }
+    }
+
+    /// Returns the version of cargo shipped in the same toolchain, which
+    /// is the same as `semver`.
+    ///
+    /// This holds for official toolchains since Rust 1.26; earlier cargo
+    /// releases were numbered `0.x` independently of rustc.
+    pub fn expected_cargo_version(&self) -> Version {
+        self.semver.clone()
+    }
+
+    /// Returns a suggestion to update the compiler if it is behind
+    /// `latest_stable`, or `None` if it is up to date.
+    ///
+    /// Stable compilers are behind if they are older than `latest_stable`.
+    /// Beta and nightly compilers are behind if their release isn't newer than
+    /// `latest_stable`, since they always precede an upcoming release. Dev
+    /// compilers are built locally and never get a suggestion.
+    pub fn recommend_upgrade(&self, latest_stable: &Version) -> Option<String> {
+        let behind = match self.channel {
+            Channel::Stable => self.semver < *latest_stable,
+            Channel::Beta | Channel::Nightly => self.stable_equivalent() <= *latest_stable,
+            Channel::Dev => false,
+        };
+        if !behind {
+            return None;
+        }
+
+        Some(format!(
+            "rustc {} is older than the latest stable release {}; consider running `rustup update {}`",
+            self.semver,
+            latest_stable,
+            self.channel.as_str()
+        ))
+    }
+
+    /// Returns, for each named threshold, whether `semver` is at least its
+    /// version, e.g. to emit a cfg for each threshold that is met.
+    ///
+    /// Versions are compared with SemVer ordering, so e.g. `1.56.0-nightly`
+    /// doesn't meet a threshold of `1.56.0`.
+    pub fn check<'a>(&self, thresholds: &[(&'a str, Version)]) -> Vec<(&'a str, bool)> {
+        thresholds
+            .iter()
+            .map(|&(name, ref min)| (name, self.semver >= *min))
+            .collect()
+    }
+
+    /// Returns the architecture of an Apple host, e.g. `aarch64` or `x86_64`
+    /// for the two macOS targets, or `None` for other hosts.
+    pub fn apple_arch(&self) -> Option<&str> {
+        if self.host_is_apple() {
+            self.host.split('-').next()
+        } else {
+            None
+        }
+    }
+
+    /// Returns the commit date of a beta compiler, or `None` for other
+    /// channels.
+    ///
+    /// Like `nightly_date`, this tells how far into its release cycle a beta
+    /// is, since betas are built from backports onto the branched nightly.
+    pub fn beta_commit_date(&self) -> Option<&str> {
+        match self.channel {
+            Channel::Beta => self.commit_date.as_ref().map(|d| d.as_str()),
+            Channel::Dev | Channel::Nightly | Channel::Stable => None,
+        }
+    }
+
+    /// Returns labeled values for displaying this version as a table, in the
+    /// order `Version`, `Channel`, `Host`, `Commit`, `Date` and `LLVM`.
+    ///
+    /// Fields that aren't known are left out.
+    pub fn summary_fields(&self) -> Vec<(&'static str, String)> {
+        let mut fields = vec![
+            ("Version", self.semver.to_string()),
+            ("Channel", self.channel.as_str().to_owned()),
+            ("Host", self.host.clone()),
+        ];
+        if let Some(ref hash) = self.commit_hash {
+            fields.push(("Commit", hash.clone()));
+        }
+        if let Some(ref date) = self.commit_date {
+            fields.push(("Date", date.clone()));
+        }
+        if let Some(ref llvm) = self.llvm_version {
+            fields.push(("LLVM", llvm.to_string()));
+        }
+        fields
+    }
+
+    /// Returns whether the compiler was built with the parallel front-end, as
+    /// reported by a `parallel: yes` or `no` line.
+    ///
+    /// Current `rustc` builds don't report this, so this is `None` for them.
+    pub fn parallel_frontend(&self) -> Option<bool> {
+        self.parallel_frontend
+    }
+
+    /// Returns a copy with `semver` replaced.
+    pub fn with_semver(mut self, semver: Version) -> Self {
+        self.semver = semver;
+        self
+    }
+
+    /// Returns a copy with `commit_hash` replaced.
+    pub fn with_commit_hash(mut self, commit_hash: Option<String>) -> Self {
+        self.commit_hash = commit_hash;
+        self
+    }
+
+    /// Returns a copy with `commit_date` replaced.
+    pub fn with_commit_date(mut self, commit_date: Option<String>) -> Self {
+        self.commit_date = commit_date;
+        self
+    }
+
+    /// Returns a copy with `build_date` replaced.
+    pub fn with_build_date(mut self, build_date: Option<String>) -> Self {
+        self.build_date = build_date;
+        self
+    }
+
+    /// Returns a copy with `channel` replaced.
+    pub fn with_channel(mut self, channel: Channel) -> Self {
+        self.channel = channel;
+        self
+    }
+
+    /// Returns a copy with `host` replaced.
+    pub fn with_host<S: Into<String>>(mut self, host: S) -> Self {
+        self.host = host.into();
+        self
+    }
+
+    /// Returns a copy with `short_version_string` replaced.
+    pub fn with_short_version_string<S: Into<String>>(mut self, short_version_string: S) -> Self {
+        self.short_version_string = short_version_string.into();
+        self
+    }
+
+    /// Returns a copy with `llvm_version` replaced.
+    ///
+    /// This also removes all other reported LLVM versions.
+    pub fn with_llvm_version(mut self, llvm_version: Option<LlvmVersion>) -> Self {
+        self.llvm_versions = NotCompared(Vec::new());
+        self.llvm_version_precision = NotCompared(None);
+        self.llvm_version = llvm_version;
+        self
+    }
+}
+
+impl AsRef<Version> for VersionMeta {
+    fn as_ref(&self) -> &Version {
+        &self.semver
+    }
+}
+
+/// A `VersionMeta` that is compared and hashed by its `semver` only.
+///
+/// `VersionMeta` can't implement `Borrow<Version>` itself, since its equality
+/// takes all of its fields into account while `Version`'s doesn't. Using this
+/// as the key of a map instead allows lookups by `Version`. Note that metas
+/// with the same version but different metadata are the same key.
+#[derive(Clone, Debug)]
+pub struct VersionKey(pub VersionMeta);
+
+impl PartialEq for VersionKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.semver == other.0.semver
+    }
+}
+
+impl Eq for VersionKey {}
+
+impl PartialOrd for VersionKey {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for VersionKey {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.semver.cmp(&other.0.semver)
+    }
+}
+
+impl Hash for VersionKey {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.semver.hash(state)
+    }
+}
+
+impl Borrow<Version> for VersionKey {
+    fn borrow(&self) -> &Version {
+        &self.0.semver
+    }
+}
+
+impl From<VersionMeta> for VersionKey {
+    fn from(meta: VersionMeta) -> Self {
+        VersionKey(meta)
+    }
+}
+
+/// Returns the oldest `rustc` version whose `-vV` output this crate is known
+/// to parse.
+pub const fn oldest_supported_rustc() -> Version {
+    Version::new(1, 0, 0)
+}
+
+/// Targets with the first LLVM major version that supports them.
+const LLVM_TARGET_MINIMUMS: &[(&str, u64)] = &[
+    ("aarch64-apple-darwin", 11),
+    ("avr-unknown-gnu-atmega328", 13),
+    ("loongarch64-unknown-linux-gnu", 16),
+    ("riscv32imac-unknown-none-elf", 9),
+    ("riscv64gc-unknown-linux-gnu", 9),
+    ("wasm32-unknown-unknown", 8),
+];
+
+/// LLVM major versions with the first and last minor version of Rust 1.x
+/// that bundled them.
+///
+/// This covers the official releases up to Rust 1.90.0, the last release of
+/// LLVM 20. Unlike the target list in `targets.rs`, it is not updated from
+/// the output of a current compiler, so newer LLVM versions are missing.
+const LLVM_RUSTC_MINORS: &[(u64, u64, u64)] = &[
+    (9, 38, 44),
+    (10, 45, 46),
+    (11, 47, 51),
+    (12, 52, 55),
+    (13, 56, 59),
+    (14, 60, 64),
+    (15, 65, 69),
+    (16, 70, 72),
+    (17, 73, 77),
+    (18, 78, 81),
+    (19, 82, 86),
+    (20, 87, 90),
+];
+
+/// Architecture prefixes with their pointer width in bits; the first match
+/// wins, so more specific prefixes come first.
+const ARCH_POINTER_WIDTHS: &[(&str, u8)] = &[
+    ("aarch64", 64),
+    ("amdgcn", 64),
+    ("arm64_32", 32),
+    ("arm64", 64),
+    ("arm", 32),
+    ("avr", 16),
+    ("bpf", 64),
+    ("csky", 32),
+    ("hexagon", 32),
+    ("i386", 32),
+    ("i586", 32),
+    ("i686", 32),
+    ("loongarch32", 32),
+    ("loongarch64", 64),
+    ("m68k", 32),
+    ("mips64", 64),
+    ("mipsisa64", 64),
+    ("mips", 32),
+    ("msp430", 16),
+    ("nvptx64", 64),
+    ("powerpc64", 64),
+    ("powerpc", 32),
+    ("riscv32", 32),
+    ("riscv64", 64),
+    ("s390x", 64),
+    ("sparc64", 64),
+    ("sparcv9", 64),
+    ("sparc", 32),
+    ("thumb", 32),
+    ("wasm32", 32),
+    ("wasm64", 64),
+    ("x86_64", 64),
+    ("xtensa", 32),
+];
+
+/// Returns the first and last `rustc` releases that bundled LLVM
+/// `llvm_major`, such as `1.47.0` and `1.51.0` for LLVM 11.
+///
+/// This is a best-effort lookup in a table of official releases up to Rust
+/// 1.90.0, covering LLVM 9 to 20; returns `None` for other versions,
+/// including the LLVM versions of later releases. Compilers built against a
+/// system LLVM may use any LLVM version they support.
+pub fn rustc_range_for_llvm(llvm_major: u64) -> Option<(Version, Version)> {
+    LLVM_RUSTC_MINORS
+        .iter()
+        .find(|&&(major, _, _)| major == llvm_major)
+        .map(|&(_, first, last)| (Version::new(1, first, 0), Version::new(1, last, 0)))
+}
+
+/// Returns the `rustc` SemVer version.
+pub fn version() -> Result<Version> {
+    Ok(version_meta()?.semver)
+}
+
+/// Returns the `rustc` SemVer version and additional metadata
+/// like the git short hash and build date.
+///
+/// `$RUSTC` is used as the path of the compiler as-is, even if it contains
+/// spaces; it is never split into a program and arguments the way a shell
+/// would. If it can't be found and looks like it contains arguments,
+/// `Error::RustcContainsArguments` is returned.
+pub fn version_meta() -> Result<VersionMeta> {
+    with_rustc(
+        env::var_os("RUSTC"),
+        env::var_os("RUSTC_WRAPPER"),
+        |mut cmd| {
+            cmd.arg("-vV");
+            version_meta_from_command(cmd)
+        },
+    )
+}
+
+/// Returns whether the `rustc` version is at least `msrv`, as given in the
+/// `rust-version` field of `Cargo.toml`, such as `"1.56"` or `"1.56.1"`.
+///
+/// Like cargo, this ignores the pre-release tag of the compiler, so
+/// `1.56.0-nightly` meets a `rust-version` of `1.56`.
+pub fn meets_package_msrv(msrv: &str) -> Result<bool> {
+    let msrv = parse_partial_version(msrv)?;
+    let version = version()?;
+    Ok((version.major, version.minor, version.patch) >= (msrv.major, msrv.minor, msrv.patch))
+}
+
+/// Returns the effective minimum version of a set of MSRVs, such as those of
+/// the crates in a workspace, i.e. the highest of them.
+///
+/// Like `meets_package_msrv`, the versions may omit the patch version, as in
+/// `"1.56"`. Returns 1.0.0 if `reqs` is empty.
+pub fn lowest_common_version(reqs: &[&str]) -> Result<Version> {
+    let mut highest = Version::new(1, 0, 0);
+    for req in reqs {
+        let version = parse_partial_version(req)?;
+        if version > highest {
+            highest = version;
+        }
+    }
+    Ok(highest)
+}
+
+/// Returns the `rustc` SemVer version and additional metadata, running
+/// `rustc` with exactly the environment variables in `vars`.
+///
+/// Like `version_meta`, but `$RUSTC` and `$RUSTC_WRAPPER` are looked up in
+/// `vars` rather than in the environment of the current process, which is
+/// not passed on to `rustc` either.
+pub fn version_meta_with_env<I>(vars: I) -> Result<VersionMeta>
+where
+    I: IntoIterator<Item = (OsString, OsString)>,
+{
+    let vars: HashMap<OsString, OsString> = vars.into_iter().collect();
+    with_rustc(
+        vars.get(OsStr::new("RUSTC")).cloned(),
+        vars.get(OsStr::new("RUSTC_WRAPPER")).cloned(),
+        |mut cmd| {
+            cmd.cmd.env_clear().envs(&vars);
+            cmd.arg("-vV");
+            version_meta_from_command(cmd)
+        },
+    )
+}
+
+/// Returns the version metadata for the `rustc` at `path`, passing `args`
+/// before `-vV`.
+///
+/// This is useful for cross toolchains that need e.g. `--sysroot` to be set.
+pub fn version_meta_for_path_with_args<P, I, S>(path: P, args: I) -> Result<VersionMeta>
+where
+    P: AsRef<Path>,
+    I: IntoIterator<Item = S>,
+    S: AsRef<OsStr>,
+{
+    let mut cmd = DescribedCommand::new(path.as_ref());
+    for arg in args {
+        cmd.arg(arg);
+    }
+    cmd.arg("-vV");
+    version_meta_from_command(cmd)
+}
+
+/// Returns the `rustdoc` SemVer version and additional metadata.
+///
+/// This runs `$RUSTDOC -vV`, falling back to `rustdoc` if `$RUSTDOC` is not
+/// set.
+pub fn rustdoc_version_meta() -> Result<VersionMeta> {
+    let rustdoc = env::var_os("RUSTDOC").unwrap_or_else(|| OsString::from("rustdoc"));
+    let mut cmd = DescribedCommand::new(rustdoc);
+    cmd.arg("-vV");
+    rustdoc_version_meta_for(&run(cmd)?)
+}
+
+/// Parses a "rustdoc -vV" output string, which has the same format as the
+/// output of `rustc -vV`.
+pub fn rustdoc_version_meta_for(verbose_version_string: &str) -> Result<VersionMeta> {
+    version_meta_for(verbose_version_string)
+}
+
+/// Returns the targets supported by `rustc`, as listed by
+/// `rustc --print target-list`.
+///
+/// The compiler is found the same way as in `version_meta`.
+pub fn target_list() -> Result<Vec<String>> {
+    with_rustc(env::var_os("RUSTC"), env::var_os("RUSTC_WRAPPER"), |cmd| {
+        print_values(cmd, "target-list")
+    })
+}
+
+/// Returns the targets supported by `cmd`, which should be a `rustc` command.
+pub fn target_list_for(cmd: Command) -> Result<Vec<String>> {
+    print_values_for(cmd, "target-list")
+}
+
+/// Returns the lines printed by `rustc --print <what>`, e.g. for `sysroot`,
+/// `cfg` or `target-list`.
+///
+/// `cmd` should be a `rustc` command. The lines are returned as-is.
+pub fn print_values_for(cmd: Command, what: &str) -> Result<Vec<String>> {
+    print_values(DescribedCommand::from_caller(cmd), what)
+}
+
+fn print_values(mut cmd: DescribedCommand, what: &str) -> Result<Vec<String>> {
+    cmd.arg("--print").arg(what);
+    Ok(run(cmd)?.lines().map(String::from).collect())
+}
+
+/// A configuration option printed by `rustc --print cfg`, such as `unix` or
+/// `target_os="linux"`.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct Cfg {
+    /// Name of the option, e.g. `target_os`
+    pub name: String,
+    /// Value of the option, e.g. `linux`, if it has one
+    pub value: Option<String>,
+}
+
+/// The version metadata and configuration options of a `rustc`, as returned
+/// by `probe_for`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Probe {
+    /// Version metadata, as returned by `rustc -vV`
+    pub meta: VersionMeta,
+    /// Configuration options, as returned by `rustc --print cfg`
+    pub cfgs: Vec<Cfg>,
+}
+
+/// Returns the version metadata and the configuration options for the host
+/// of the `rustc` at `rustc`.
+///
+/// Both are queried from the same binary, so they are consistent with each
+/// other.
+pub fn probe_for(rustc: &Path) -> Result<Probe> {
+    let mut cmd = DescribedCommand::new(rustc);
+    cmd.arg("-vV");
+    let meta = version_meta_from_command(cmd)?;
+    let cfgs = print_values(DescribedCommand::new(rustc), "cfg")?
+        .iter()
+        .map(|line| {
+            let mut parts = line.splitn(2, '=');
+            let name = parts.next().unwrap_or("").to_owned();
+            let value = parts.next().map(|v| v.trim_matches('"').to_owned());
+            Cfg { name, value }
+        })
+        .collect();
+    Ok(Probe { meta, cfgs })
+}
+
+/// Returns whether the `rustc` at `rustc` accepts `#![feature(<feature>)]`,
+/// by compiling a library containing only that attribute.
 ///
-/// LLVM's version numbering scheme is not semver compatible until version 4.0
+/// This is the most reliable way to check whether an unstable feature is
+/// available. Stable and beta compilers reject all feature attributes unless
+/// `RUSTC_BOOTSTRAP` is set; nightly compilers reject unknown features. The
+/// compiler output is discarded; only metadata is emitted, into a temporary
+/// directory.
 ///
-/// rustc [just prints the major and minor versions], so other parts of the version are not included.
+/// `feature` must be a plain identifier, or `Error::InvalidFeatureName` is
+/// returned.
+pub fn feature_accepted_for(rustc: &Path, feature: &str) -> Result<bool> {
+    // the name is pasted into the source, so don't allow anything but an
+    // identifier
+    let is_ident_char = |c: char| c.is_ascii_alphanumeric() || c == '_';
+    if feature.is_empty() || !feature.chars().all(is_ident_char) {
+        return Err(InvalidFeatureName(feature.to_owned()));
+    }
+
+    // concurrent probes must not share a directory, since each removes its own
+    static PROBE_COUNT: AtomicUsize = AtomicUsize::new(0);
+    let out_dir = env::temp_dir().join(format!(
+        "rustc_version-feature-{}-{}",
+        process::id(),
+        PROBE_COUNT.fetch_add(1, atomic::Ordering::Relaxed)
+    ));
+    fs::create_dir_all(&out_dir).map_err(CouldNotWriteFile)?;
+
+    let mut cmd = DescribedCommand::new(rustc);
+    for &arg in &["--crate-type", "lib", "--crate-name", "feature_probe"] {
+        cmd.arg(arg);
+    }
+    cmd.arg("--emit").arg("metadata").arg("--out-dir");
+    cmd.arg(&out_dir).arg("-");
+    let DescribedCommand {
+        mut cmd,
+        description: command,
+    } = cmd;
+    cmd.stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    let status = cmd.spawn().and_then(|mut child| {
+        if let Some(mut stdin) = child.stdin.take() {
+            // a failed write shows up as a compile error
+            let _ = writeln!(stdin, "#![feature({})]", feature);
+        }
+        child.wait()
+    });
+    let _ = fs::remove_dir_all(&out_dir);
+
+    let status = status.map_err(|source| CouldNotExecuteCommand { command, source })?;
+    Ok(status.success())
+}
+
+/// Returns the target specification of `target` as JSON, as printed by
+/// `rustc -Z unstable-options --print target-spec-json --target <target>`.
 ///
-/// [just prints the major and minor versions]: https://github.com/rust-lang/rust/blob/b5c9e2448c9ace53ad5c11585803894651b18b0a/compiler/rustc_codegen_llvm/src/llvm_util.rs#L173-L178
-#[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
-pub struct LlvmVersion {
-    // fields must be ordered major, minor for comparison to be correct
-    /// Major version
-    pub major: u64,
-    /// Minor version
-    pub minor: u64,
-    // TODO: expose micro version here
+/// `cmd` should be a `rustc` command. Since this uses an unstable option, it
+/// only works with a nightly or dev compiler, or with `RUSTC_BOOTSTRAP=1`
+/// set; other compilers fail with `Error::CommandError`. The JSON is
+/// returned as-is.
+pub fn target_spec_json_for(cmd: Command, target: &str) -> Result<String> {
+    let mut cmd = DescribedCommand::from_caller(cmd);
+    for &arg in &["-Z", "unstable-options", "--print", "target-spec-json"] {
+        cmd.arg(arg);
+    }
+    cmd.arg("--target").arg(target);
+    run(cmd)
 }
 
-impl fmt::Display for LlvmVersion {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}.{}", self.major, self.minor)
+/// Prints the directives asking cargo to rerun the build script when the
+/// compiler selected through `$RUSTC` or `$RUSTC_WRAPPER` changes.
+///
+/// Call this from a build script that bases decisions on `version_meta()`.
+/// The printed directives are listed by `rerun_if_changed_directives`.
+pub fn emit_rerun_if_changed() {
+    for directive in rerun_if_changed_directives() {
+        println!("{}", directive);
     }
 }
 
-impl FromStr for LlvmVersion {
-    type Err = LlvmVersionParseError;
+/// Returns the directives printed by `emit_rerun_if_changed`.
+pub fn rerun_if_changed_directives() -> &'static [&'static str] {
+    &[
+        "cargo:rerun-if-env-changed=RUSTC",
+        "cargo:rerun-if-env-changed=RUSTC_WRAPPER",
+    ]
+}
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut parts = s
-            .split('.')
-            .map(|part| -> Result<u64, LlvmVersionParseError> {
-                if part == "0" {
-                    Ok(0)
-                } else if part.starts_with('0') {
-                    Err(LlvmVersionParseError::ComponentMustNotHaveLeadingZeros)
-                } else if part.starts_with('-') || part.starts_with('+') {
-                    Err(LlvmVersionParseError::ComponentMustNotHaveSign)
-                } else {
-                    Ok(part.parse()?)
-                }
-            });
+/// Writes a Rust source file to `out` defining constants that describe
+/// `rustc`, for use from a build script.
+///
+/// The file defines `RUSTC_SEMVER`, `RUSTC_CHANNEL` and `RUSTC_HOST` as
+/// `&str`, and `RUSTC_COMMIT_HASH`, `RUSTC_COMMIT_DATE` and
+/// `RUSTC_LLVM_VERSION` as `Option<&str>`. It is meant to be included in the
+/// crate being built:
+///
+/// ```rust,ignore
+/// include!(concat!(env!("OUT_DIR"), "/rustc_version.rs"));
+/// ```
+pub fn write_version_constants<P: AsRef<Path>>(out: P) -> Result<()> {
+    let meta = version_meta()?;
 
-        let major = parts.next().unwrap()?;
-        let mut minor = 0;
+    let mut src = String::from("// Generated by rustc_version, do not edit.\n");
+    let mut constant = |name: &str, desc: &str, ty: &str, value: String| {
+        src.push_str(&format!(
+            "\n/// {} of the rustc used for the build.\npub const {}: {} = {};\n",
+            desc, name, ty, value
+        ));
+    };
+    let optional = |value: Option<String>| match value {
+        Some(v) => format!("Some({:?})", v),
+        None => "None".to_owned(),
+    };
 
-        if let Some(part) = parts.next() {
-            minor = part?;
-        } else if major < 4 {
-            // LLVM versions earlier than 4.0 have significant minor versions, so require the minor version in this case.
-            return Err(LlvmVersionParseError::MinorVersionRequiredBefore4);
+    constant(
+        "RUSTC_SEMVER",
+        "Version",
+        "&str",
+        format!("{:?}", meta.semver.to_string()),
+    );
+    constant(
+        "RUSTC_CHANNEL",
+        "Release channel",
+        "&str",
+        format!("{:?}", meta.channel.as_str()),
+    );
+    constant(
+        "RUSTC_HOST",
+        "Host target triple",
+        "&str",
+        format!("{:?}", meta.host),
+    );
+    constant(
+        "RUSTC_COMMIT_HASH",
+        "Git commit hash",
+        "Option<&str>",
+        optional(meta.commit_hash),
+    );
+    constant(
+        "RUSTC_COMMIT_DATE",
+        "Commit date",
+        "Option<&str>",
+        optional(meta.commit_date),
+    );
+    constant(
+        "RUSTC_LLVM_VERSION",
+        "LLVM version",
+        "Option<&str>",
+        optional(meta.llvm_version.map(|v| v.to_string())),
+    );
+
+    fs::write(out, src).map_err(CouldNotWriteFile)
+}
+
+/// Reads `rustc -vV` output from the file at `path` and parses it like
+/// `version_meta_for`.
+///
+/// This allows builds to use a version recorded earlier, e.g. with
+/// `rustc -vV > .rustc-version`, without running rustc.
+pub fn version_meta_from_file<P: AsRef<Path>>(path: P) -> Result<VersionMeta> {
+    let contents = fs::read_to_string(path).map_err(CouldNotReadFile)?;
+    version_meta_for(&contents)
+}
+
+/// Calls `f` with the command for `rustc` (defaulting to `rustc`), wrapped in
+/// `wrapper` if that is set.
+fn with_rustc<T, F>(rustc: Option<OsString>, wrapper: Option<OsString>, f: F) -> Result<T>
+where
+    F: FnOnce(DescribedCommand) -> Result<T>,
+{
+    let rustc = rustc.unwrap_or_else(|| OsString::from("rustc"));
+    if let Some(wrapper) = wrapper.filter(|w| !w.is_empty()) {
+        let mut cmd = DescribedCommand::new(wrapper);
+        cmd.arg(rustc);
+        return f(cmd);
+    }
+
+    f(DescribedCommand::new(&rustc)).map_err(|e| match e {
+        CouldNotExecuteCommand {
+            source: ref io_err, ..
+        } if io_err.kind() == io::ErrorKind::NotFound && rustc.to_string_lossy().contains(" -") => {
+            RustcContainsArguments(rustc)
         }
+        e => e,
+    })
+}
+
+/// A command along with a description of its program and arguments for error
+/// messages.
+///
+/// The `Debug` output of `Command` can't be used for this, since it includes
+/// any environment variables that were set, which may hold secrets.
+struct DescribedCommand {
+    cmd: Command,
+    description: String,
+}
 
-        if let Some(Err(e)) = parts.next() {
-            return Err(e);
+impl DescribedCommand {
+    fn new<S: AsRef<OsStr>>(program: S) -> DescribedCommand {
+        DescribedCommand {
+            cmd: Command::new(&program),
+            description: format!("{:?}", program.as_ref()),
         }
+    }
 
-        if parts.next().is_some() {
-            return Err(LlvmVersionParseError::TooManyComponents);
+    /// Wraps a command passed in by the caller, whose program and arguments
+    /// are unknown.
+    fn from_caller(cmd: Command) -> DescribedCommand {
+        DescribedCommand {
+            cmd,
+            description: "<command>".to_owned(),
         }
+    }
 
-        Ok(Self { major, minor })
+    fn arg<S: AsRef<OsStr>>(&mut self, arg: S) -> &mut DescribedCommand {
+        self.description += &format!(" {:?}", arg.as_ref());
+        self.cmd.arg(arg);
+        self
     }
 }
 
-/// Rustc version plus metadata like git short hash and build date.
-#[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
-pub struct VersionMeta {
-    /// Version of the compiler
-    pub semver: Version,
+/// Runs `cmd` and parses its output as `rustc -vV` output.
+fn version_meta_from_command(cmd: DescribedCommand) -> Result<VersionMeta> {
+    let out = run(cmd)?;
+    version_meta_for(&out).map_err(|e| match e {
+        UnexpectedVersionFormat => UnexpectedOutput(output_snippet(&out)),
+        e => e,
+    })
+}
 
-    /// Git short hash of the build of the compiler
-    pub commit_hash: Option<String>,
+/// Runs `cmd` and returns its output, failing if it didn't exit successfully.
+fn run(cmd: DescribedCommand) -> Result<String> {
+    let DescribedCommand {
+        mut cmd,
+        description: command,
+    } = cmd;
+    let out = match cmd.output() {
+        Ok(out) => out,
+        Err(source) => return Err(Error::CouldNotExecuteCommand { command, source }),
+    };
 
-    /// Commit date of the compiler
-    pub commit_date: Option<String>,
+    if !out.status.success() {
+        return Err(Error::CommandError {
+            command,
+            stdout: String::from_utf8_lossy(&out.stdout).into(),
+            stderr: String::from_utf8_lossy(&out.stderr).into(),
+        });
+    }
 
-    /// Build date of the compiler; this was removed between Rust 1.0.0 and 1.1.0.
-    pub build_date: Option<String>,
+    Ok(str::from_utf8(&out.stdout)?.to_owned())
+}
 
-    /// Release channel of the compiler
-    pub channel: Channel,
+/// Parses a "rustc -vV" output string and returns
+/// the SemVer version and additional metadata
+/// like the git short hash and build date.
+///
+/// The output of `miri -vV`, which prefixes the rustc output with a line
+/// for Miri's own version, is accepted too and describes the underlying rustc.
+pub fn version_meta_for(verbose_version_string: &str) -> Result<VersionMeta> {
+    version_meta_for_with_options(verbose_version_string, &ParseOptions::new())
+}
 
-    /// Host target triple of the compiler
-    pub host: String,
+/// Parses a "rustc -vV" output string like `version_meta_for`, but reports
+/// every problem found instead of stopping at the first one.
+///
+/// Problems that only affect a single field, such as a malformed LLVM
+/// version or missing commit info, are collected while the remaining fields
+/// are still parsed. In that case the returned `VersionMeta` is incomplete:
+/// the affected fields are `None`, and an unknown channel is reported as
+/// `Channel::Dev`. If a required field like `release` can't be parsed, no
+/// `VersionMeta` is returned, and the fields after it aren't checked.
+///
+/// The errors are in the order they are checked in, so the first one is the
+/// error `version_meta_for` returns.
+pub fn version_meta_for_partial(verbose_version_string: &str) -> (Option<VersionMeta>, Vec<Error>) {
+    let mut errors = Vec::new();
+    let result =
+        parse_verbose_collecting(verbose_version_string, &ParseOptions::new(), &mut errors);
+    match result {
+        Ok(version) => (Some(version), errors),
+        Err(e) => {
+            errors.push(e);
+            (None, errors)
+        }
+    }
+}
 
-    /// Short version string of the compiler
-    pub short_version_string: String,
+/// Options controlling how strictly `version_meta_for_with_options` parses
+/// `rustc -vV` output.
+///
+/// The defaults match `version_meta_for`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ParseOptions {
+    allow_extra_lines: bool,
+    allow_unknown_channel: bool,
+    require_commit_hash: bool,
+    allow_partial_release: bool,
+    allow_llvm_suffix: bool,
+    ignore_key_case: bool,
+    allow_missing_commit_info: bool,
+}
 
-    /// Version of LLVM used by the compiler
-    pub llvm_version: Option<LlvmVersion>,
+impl ParseOptions {
+    /// Returns the default options.
+    pub fn new() -> ParseOptions {
+        ParseOptions::default()
+    }
+
+    /// Skip any lines preceding the version line of `rustc`, or of another
+    /// tool like `cargo` or `miri`, as done by `version_meta_for_lenient`.
+    /// Defaults to `false`.
+    pub fn allow_extra_lines(mut self, allow: bool) -> ParseOptions {
+        self.allow_extra_lines = allow;
+        self
+    }
+
+    /// Report unknown pre-release tags as the dev channel, as done by
+    /// `version_meta_for_lossy`. Defaults to `false`.
+    pub fn allow_unknown_channel(mut self, allow: bool) -> ParseOptions {
+        self.allow_unknown_channel = allow;
+        self
+    }
+
+    /// Fail with `Error::UnexpectedVersionFormat` if the commit hash is
+    /// `unknown`. Defaults to `false`.
+    pub fn require_commit_hash(mut self, require: bool) -> ParseOptions {
+        self.require_commit_hash = require;
+        self
+    }
+
+    /// Accept a release version without a patch version, as in `1.70`, and
+    /// treat the patch version as `0`. Defaults to `false`.
+    pub fn allow_partial_release(mut self, allow: bool) -> ParseOptions {
+        self.allow_partial_release = allow;
+        self
+    }
+
+    /// Accept LLVM versions with a `-rust` suffix, as in
+    /// `17.0.6-rust-1.80.0-stable`, using `LlvmVersion::parse_lenient`.
+    /// Defaults to `false`.
+    pub fn allow_llvm_suffix(mut self, allow: bool) -> ParseOptions {
+        self.allow_llvm_suffix = allow;
+        self
+    }
+
+    /// Match the known keys, like `host`, regardless of ASCII case, as in
+    /// `Host: x86_64-unknown-linux-gnu`. Defaults to `false`.
+    pub fn ignore_key_case(mut self, ignore: bool) -> ParseOptions {
+        self.ignore_key_case = ignore;
+        self
+    }
+
+    /// Accept output without `commit-hash` and `commit-date` lines, treating
+    /// them like `unknown`. Defaults to `false`.
+    pub fn allow_missing_commit_info(mut self, allow: bool) -> ParseOptions {
+        self.allow_missing_commit_info = allow;
+        self
+    }
 }
 
-impl VersionMeta {
-    /// Returns the version metadata for `cmd`, which should be a `rustc` command.
-    pub fn for_command(mut cmd: Command) -> Result<VersionMeta> {
-        let out = cmd
-            .arg("-vV")
-            .output()
-            .map_err(Error::CouldNotExecuteCommand)?;
-
-        if !out.status.success() {
-            return Err(Error::CommandError {
-                stdout: String::from_utf8_lossy(&out.stdout).into(),
-                stderr: String::from_utf8_lossy(&out.stderr).into(),
-            });
+/// Parses a "rustc -vV" output string like `version_meta_for`, using the given
+/// options.
+pub fn version_meta_for_with_options(
+    verbose_version_string: &str,
+    options: &ParseOptions,
+) -> Result<VersionMeta> {
+    let mut start = 0;
+    if options.allow_extra_lines {
+        for line in verbose_version_string.split('\n') {
+            if compiler_kind(line).is_some() {
+                break;
+            }
+            start += line.len() + 1;
+        }
+        if start > verbose_version_string.len() {
+            // no version line, so report the first line
+            start = 0;
         }
-
-        version_meta_for(str::from_utf8(&out.stdout)?)
     }
+
+    let mut meta = parse_verbose(&verbose_version_string[start..], options)?;
+    // keep any skipped lines as well
+    meta.raw = NotCompared(Some(verbose_version_string.to_owned()));
+    Ok(meta)
 }
 
-/// Returns the `rustc` SemVer version.
-pub fn version() -> Result<Version> {
-    Ok(version_meta()?.semver)
+/// Like `version_meta_for`, but reports compilers with an unknown pre-release
+/// tag, such as `1.80.0-experimental`, as being on the dev channel instead of
+/// failing with `Error::UnknownPreReleaseTag`.
+///
+/// The original tag is still available from `semver.pre`.
+pub fn version_meta_for_lossy(verbose_version_string: &str) -> Result<VersionMeta> {
+    let options = ParseOptions::new().allow_unknown_channel(true);
+    version_meta_for_with_options(verbose_version_string, &options)
 }
 
-/// Returns the `rustc` SemVer version and additional metadata
-/// like the git short hash and build date.
-pub fn version_meta() -> Result<VersionMeta> {
-    let rustc = env::var_os("RUSTC").unwrap_or_else(|| OsString::from("rustc"));
-    let cmd = if let Some(wrapper) = env::var_os("RUSTC_WRAPPER").filter(|w| !w.is_empty()) {
-        let mut cmd = Command::new(wrapper);
-        cmd.arg(rustc);
-        cmd
-    } else {
-        Command::new(rustc)
-    };
+/// Keys of `rustc -vV` output that are read by `parse_verbose`.
+const KNOWN_KEYS: &[&str] = &[
+    "binary",
+    "commit-hash",
+    "commit-date",
+    "build-date",
+    "rust-birthday",
+    "host",
+    "release",
+    "LLVM version",
+    "debug-assertions",
+    "parallel",
+];
 
-    VersionMeta::for_command(cmd)
+fn parse_verbose(verbose_version_string: &str, options: &ParseOptions) -> Result<VersionMeta> {
+    let mut errors = Vec::new();
+    let result = parse_verbose_collecting(verbose_version_string, options, &mut errors);
+    if errors.is_empty() {
+        result
+    } else {
+        Err(errors.remove(0))
+    }
 }
 
-/// Parses a "rustc -vV" output string and returns
-/// the SemVer version and additional metadata
-/// like the git short hash and build date.
-pub fn version_meta_for(verbose_version_string: &str) -> Result<VersionMeta> {
+/// Parses like `parse_verbose`, but pushes errors that still leave a usable
+/// value for the affected field onto `errors` instead of returning them.
+fn parse_verbose_collecting(
+    verbose_version_string: &str,
+    options: &ParseOptions,
+    errors: &mut Vec<Error>,
+) -> Result<VersionMeta> {
+    if verbose_version_string.trim().is_empty() {
+        return Err(Error::EmptyOutput);
+    }
+
+    let mut lines = verbose_version_string.lines().peekable();
+    let kind = match lines.peek().cloned().and_then(compiler_kind) {
+        Some(CompilerKind::Miri) => {
+            // Miri's own version precedes the output of the underlying rustc
+            lines.next();
+            CompilerKind::Miri
+        }
+        Some(kind) => kind,
+        None => {
+            let line = lines.next().unwrap_or("");
+            return Err(Error::NotACompilerVersion(line.to_owned()));
+        }
+    };
+
     let mut map = HashMap::new();
-    for (i, line) in verbose_version_string.lines().enumerate() {
+    let mut llvm_values = Vec::new();
+    for (i, line) in lines.enumerate() {
         if i == 0 {
             map.insert("short", line);
             continue;
         }
 
         let mut parts = line.splitn(2, ": ");
-        let key = match parts.next() {
+        let mut key = match parts.next() {
             Some(key) => key,
             None => continue,
         };
+        if options.ignore_key_case {
+            if let Some(known) = KNOWN_KEYS.iter().find(|k| k.eq_ignore_ascii_case(key)) {
+                key = known;
+            }
+        }
 
         if let Some(value) = parts.next() {
+            // captured output may have trailing whitespace
+            let value = value.trim_end();
+            if key == "LLVM version" {
+                llvm_values.push(value);
+            }
             map.insert(key, value);
         }
     }
@@ -235,26 +1987,69 @@ pub fn version_meta_for(verbose_version_string: &str) -> Result<VersionMeta> {
     let short_version_string = expect_key("short", &map)?;
     let host = expect_key("host", &map)?;
     let release = expect_key("release", &map)?;
-    let semver: Version = release.parse()?;
+    let semver = if options.allow_partial_release {
+        parse_partial_version(&release)?
+    } else {
+        release.parse()?
+    };
 
     let channel = match semver.pre.split('.').next().unwrap() {
         "" => Channel::Stable,
         "dev" => Channel::Dev,
-        "beta" => Channel::Beta,
+        "beta" | "rc" => Channel::Beta,
         "nightly" => Channel::Nightly,
-        x => return Err(Error::UnknownPreReleaseTag(x.to_owned())),
+        _ if options.allow_unknown_channel => Channel::Dev,
+        x => {
+            errors.push(Error::UnknownPreReleaseTag(x.to_owned()));
+            Channel::Dev
+        }
     };
 
-    let commit_hash = expect_key_or_unknown("commit-hash", &map)?;
-    let commit_date = expect_key_or_unknown("commit-date", &map)?;
+    let commit_info = |key, errors: &mut Vec<Error>| {
+        if options.allow_missing_commit_info && !map.contains_key(key) {
+            return None;
+        }
+        expect_key_or_unknown(key, &map).unwrap_or_else(|e| {
+            errors.push(e);
+            None
+        })
+    };
+    let errors_before = errors.len();
+    let commit_hash = commit_info("commit-hash", errors);
+    // a missing line was already reported
+    if options.require_commit_hash && commit_hash.is_none() && errors.len() == errors_before {
+        errors.push(Error::UnexpectedVersionFormat);
+    }
+    let commit_date = commit_info("commit-date", errors);
+    // Pre-1.0 compilers called the build date `rust-birthday`
     let build_date = map
         .get("build-date")
+        .or_else(|| map.get("rust-birthday"))
         .filter(|&v| *v != "unknown")
         .map(|&v| String::from(v));
-    let llvm_version = match map.get("LLVM version") {
-        Some(&v) => Some(v.parse()?),
-        None => None,
-    };
+    let mut llvm_versions = Vec::new();
+    let mut llvm_version_precision = None;
+    for value in llvm_values {
+        let llvm_version = if options.allow_llvm_suffix {
+            LlvmVersion::parse_lenient(value)
+        } else {
+            value.parse()
+        };
+        match llvm_version {
+            Ok(llvm_version) => llvm_versions.push(llvm_version),
+            Err(e) => {
+                errors.push(e.into());
+                continue;
+            }
+        }
+        if llvm_version_precision.is_none() {
+            let numeric = value.split("-rust").next().unwrap_or(value);
+            llvm_version_precision = Some(numeric.split('.').count() as u8);
+        }
+    }
+    let llvm_version = llvm_versions.first().cloned();
+    let debug_assertions = parse_flag(map.get("debug-assertions"));
+    let parallel_frontend = parse_flag(map.get("parallel"));
 
     Ok(VersionMeta {
         semver,
@@ -265,9 +2060,236 @@ pub fn version_meta_for(verbose_version_string: &str) -> Result<VersionMeta> {
         host,
         short_version_string,
         llvm_version,
+        raw: NotCompared(Some(verbose_version_string.to_owned())),
+        llvm_versions: NotCompared(llvm_versions),
+        llvm_version_precision: NotCompared(llvm_version_precision),
+        kind: NotCompared(kind),
+        debug_assertions,
+        parallel_frontend,
     })
 }
 
+/// Like `version_meta_for`, but skips any lines preceding the version line of
+/// `rustc`, or of another tool like `cargo` or `miri`.
+///
+/// This allows parsing output that was captured together with stderr, where
+/// warnings may be printed before the version information.
+pub fn version_meta_for_lenient(verbose_version_string: &str) -> Result<VersionMeta> {
+    let options = ParseOptions::new().allow_extra_lines(true);
+    version_meta_for_with_options(verbose_version_string, &options)
+}
+
+/// Returns the tool whose version output starts with `line`, judging by the
+/// program name at its start.
+fn compiler_kind(line: &str) -> Option<CompilerKind> {
+    match line.split(' ').next() {
+        Some("miri") => Some(CompilerKind::Miri),
+        Some("cargo") => Some(CompilerKind::Cargo),
+        Some("rustdoc") => Some(CompilerKind::Rustdoc),
+        Some("rustc") => Some(CompilerKind::Rustc),
+        _ => None,
+    }
+}
+
+/// A value that is ignored when comparing or hashing the struct holding it.
+#[derive(Clone, Debug)]
+struct NotCompared<T>(T);
+
+impl<T> PartialEq for NotCompared<T> {
+    fn eq(&self, _: &NotCompared<T>) -> bool {
+        true
+    }
+}
+
+impl<T> Eq for NotCompared<T> {}
+
+impl<T> PartialOrd for NotCompared<T> {
+    fn partial_cmp(&self, other: &NotCompared<T>) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> Ord for NotCompared<T> {
+    fn cmp(&self, _: &NotCompared<T>) -> Ordering {
+        Ordering::Equal
+    }
+}
+
+impl<T> Hash for NotCompared<T> {
+    fn hash<H: Hasher>(&self, _: &mut H) {}
+}
+
+/// Parses a `yes` or `no` value, as reported for optional compiler features.
+fn parse_flag(value: Option<&&str>) -> Option<bool> {
+    match value {
+        Some(&"yes") | Some(&"true") => Some(true),
+        Some(&"no") | Some(&"false") => Some(false),
+        _ => None,
+    }
+}
+
+/// Parses `s` as a version, treating a missing patch version as `.0`.
+fn parse_partial_version(s: &str) -> Result<Version> {
+    let s = s.trim();
+    let core_len = s.find(|c| c == '-' || c == '+').unwrap_or(s.len());
+    let (core, rest) = s.split_at(core_len);
+    if core.matches('.').count() == 1 {
+        Ok(format!("{}.0{}", core, rest).parse()?)
+    } else {
+        Ok(s.parse()?)
+    }
+}
+
+/// Returns the first few lines of `out`, for use in error messages.
+fn output_snippet(out: &str) -> String {
+    const MAX_LINES: usize = 5;
+    const MAX_CHARS: usize = 500;
+
+    let lines: Vec<_> = out.trim_end().lines().collect();
+    let mut snippet = lines[..lines.len().min(MAX_LINES)].join("\n");
+    if snippet.chars().count() > MAX_CHARS {
+        snippet = snippet.chars().take(MAX_CHARS).collect();
+    } else if lines.len() <= MAX_LINES {
+        return snippet;
+    }
+    snippet.push_str("\n...");
+    snippet
+}
+
+type JsonChars<'a> = std::iter::Peekable<str::Chars<'a>>;
+
+/// Parses a flat JSON object whose values are strings or `null`, as written
+/// by `VersionMeta::to_json`.
+fn parse_json_object(s: &str) -> Result<HashMap<String, Option<String>>> {
+    let mut chars = s.chars().peekable();
+    let mut map = HashMap::new();
+
+    json_expect(&mut chars, '{')?;
+    if json_peek(&mut chars) == Some('}') {
+        chars.next();
+    } else {
+        loop {
+            let key = json_parse_string(&mut chars)?;
+            json_expect(&mut chars, ':')?;
+            let value = if json_peek(&mut chars) == Some('n') {
+                if !"null".chars().all(|c| chars.next() == Some(c)) {
+                    return Err(InvalidJson("expected a string or `null`".to_owned()));
+                }
+                None
+            } else {
+                Some(json_parse_string(&mut chars)?)
+            };
+            map.insert(key, value);
+
+            match json_peek(&mut chars) {
+                Some(',') => chars.next(),
+                Some('}') => {
+                    chars.next();
+                    break;
+                }
+                _ => return Err(InvalidJson("expected `,` or `}`".to_owned())),
+            };
+        }
+    }
+
+    if json_peek(&mut chars).is_some() {
+        return Err(InvalidJson("trailing characters".to_owned()));
+    }
+    Ok(map)
+}
+
+/// Skips whitespace and returns the next character without consuming it.
+fn json_peek(chars: &mut JsonChars<'_>) -> Option<char> {
+    while chars.peek().map_or(false, |c| c.is_whitespace()) {
+        chars.next();
+    }
+    chars.peek().cloned()
+}
+
+fn json_expect(chars: &mut JsonChars<'_>, expected: char) -> Result<()> {
+    if json_peek(chars) == Some(expected) {
+        chars.next();
+        Ok(())
+    } else {
+        Err(InvalidJson(format!("expected `{}`", expected)))
+    }
+}
+
+fn json_parse_string(chars: &mut JsonChars<'_>) -> Result<String> {
+    json_expect(chars, '"')?;
+    let mut out = String::new();
+    loop {
+        match chars.next() {
+            Some('"') => return Ok(out),
+            Some('\\') => {
+                let c = match chars.next() {
+                    Some('"') => '"',
+                    Some('\\') => '\\',
+                    Some('/') => '/',
+                    Some('b') => '\u{8}',
+                    Some('f') => '\u{c}',
+                    Some('n') => '\n',
+                    Some('r') => '\r',
+                    Some('t') => '\t',
+                    Some('u') => {
+                        let mut code = json_parse_hex4(chars)?;
+                        if code >= 0xd800 && code < 0xdc00 {
+                            if chars.next() != Some('\\') || chars.next() != Some('u') {
+                                return Err(InvalidJson("unpaired surrogate".to_owned()));
+                            }
+                            let low = json_parse_hex4(chars)?;
+                            if low < 0xdc00 || low >= 0xe000 {
+                                return Err(InvalidJson("unpaired surrogate".to_owned()));
+                            }
+                            code = 0x10000 + ((code - 0xd800) << 10) + (low - 0xdc00);
+                        }
+                        std::char::from_u32(code)
+                            .ok_or_else(|| InvalidJson("invalid `\\u` escape".to_owned()))?
+                    }
+                    _ => return Err(InvalidJson("invalid escape".to_owned())),
+                };
+                out.push(c);
+            }
+            Some(c) if (c as u32) < 0x20 => {
+                return Err(InvalidJson("unescaped control character".to_owned()))
+            }
+            Some(c) => out.push(c),
+            None => return Err(InvalidJson("unterminated string".to_owned())),
+        }
+    }
+}
+
+fn json_parse_hex4(chars: &mut JsonChars<'_>) -> Result<u32> {
+    let mut code = 0;
+    for _ in 0..4 {
+        let digit = chars
+            .next()
+            .and_then(|c| c.to_digit(16))
+            .ok_or_else(|| InvalidJson("invalid `\\u` escape".to_owned()))?;
+        code = code * 16 + digit;
+    }
+    Ok(code)
+}
+
+/// Quotes `s` as a JSON string.
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
 fn expect_key_or_unknown(key: &str, map: &HashMap<&str, &str>) -> Result<Option<String>, Error> {
     match map.get(key) {
         Some(&"unknown") => Ok(None),
@@ -287,16 +2309,18 @@ fn expect_key(key: &str, map: &HashMap<&str, &str>) -> Result<String, Error> {
 pub enum LlvmVersionParseError {
     /// An error occurred in parsing a version component as an integer
     ParseIntError(num::ParseIntError),
-    /// A version component must not have leading zeros
-    ComponentMustNotHaveLeadingZeros,
-    /// A version component has a sign
-    ComponentMustNotHaveSign,
-    /// Minor version component must be zero on LLVM versions later than 4.0
-    MinorVersionMustBeZeroAfter4,
-    /// Minor version component is required on LLVM versions earlier than 4.0
-    MinorVersionRequiredBefore4,
-    /// Too many components
-    TooManyComponents,
+    /// A version component must not have leading zeros; holds the component
+    ComponentMustNotHaveLeadingZeros(String),
+    /// A version component has a sign; holds the component
+    ComponentMustNotHaveSign(String),
+    /// Minor version component must be zero on LLVM versions later than 4.0;
+    /// holds the version
+    MinorVersionMustBeZeroAfter4(String),
+    /// Minor version component is required on LLVM versions earlier than 4.0;
+    /// holds the version
+    MinorVersionRequiredBefore4(String),
+    /// Too many components; holds the version
+    TooManyComponents(String),
 }
 
 impl From<num::ParseIntError> for LlvmVersionParseError {
@@ -311,21 +2335,27 @@ impl fmt::Display for LlvmVersionParseError {
             LlvmVersionParseError::ParseIntError(e) => {
                 write!(f, "error parsing LLVM version component: {}", e)
             }
-            LlvmVersionParseError::ComponentMustNotHaveLeadingZeros => {
-                write!(f, "a version component must not have leading zeros")
-            }
-            LlvmVersionParseError::ComponentMustNotHaveSign => {
-                write!(f, "a version component must not have a sign")
+            LlvmVersionParseError::ComponentMustNotHaveLeadingZeros(c) => write!(
+                f,
+                "a version component must not have leading zeros: `{}`",
+                c
+            ),
+            LlvmVersionParseError::ComponentMustNotHaveSign(c) => {
+                write!(f, "a version component must not have a sign: `{}`", c)
             }
-            LlvmVersionParseError::MinorVersionMustBeZeroAfter4 => write!(
+            LlvmVersionParseError::MinorVersionMustBeZeroAfter4(v) => write!(
                 f,
-                "LLVM's minor version component must be 0 for versions greater than 4.0"
+                "LLVM's minor version component must be 0 for versions greater than 4.0: `{}`",
+                v
             ),
-            LlvmVersionParseError::MinorVersionRequiredBefore4 => write!(
+            LlvmVersionParseError::MinorVersionRequiredBefore4(v) => write!(
                 f,
-                "LLVM's minor version component is required for versions less than 4.0"
+                "LLVM's minor version component is required for versions less than 4.0: `{}`",
+                v
             ),
-            LlvmVersionParseError::TooManyComponents => write!(f, "too many version components"),
+            LlvmVersionParseError::TooManyComponents(v) => {
+                write!(f, "too many version components: `{}`", v)
+            }
         }
     }
 }
@@ -334,11 +2364,11 @@ impl error::Error for LlvmVersionParseError {
     fn source(&self) -> Option<&(dyn error::Error + 'static)> {
         match self {
             LlvmVersionParseError::ParseIntError(e) => Some(e),
-            LlvmVersionParseError::ComponentMustNotHaveLeadingZeros
-            | LlvmVersionParseError::ComponentMustNotHaveSign
-            | LlvmVersionParseError::MinorVersionMustBeZeroAfter4
-            | LlvmVersionParseError::MinorVersionRequiredBefore4
-            | LlvmVersionParseError::TooManyComponents => None,
+            LlvmVersionParseError::ComponentMustNotHaveLeadingZeros(_)
+            | LlvmVersionParseError::ComponentMustNotHaveSign(_)
+            | LlvmVersionParseError::MinorVersionMustBeZeroAfter4(_)
+            | LlvmVersionParseError::MinorVersionRequiredBefore4(_)
+            | LlvmVersionParseError::TooManyComponents(_) => None,
         }
     }
 }
@@ -347,9 +2377,19 @@ impl error::Error for LlvmVersionParseError {
 #[derive(Debug)]
 pub enum Error {
     /// An error occurred while trying to find the `rustc` to run.
-    CouldNotExecuteCommand(io::Error),
+    CouldNotExecuteCommand {
+        /// the program that was run, with its arguments; for a command passed
+        /// in by the caller, only the arguments added by this crate, after
+        /// `<command>`
+        command: String,
+        /// the error from running the command
+        source: io::Error,
+    },
     /// Error output from the command that was run.
     CommandError {
+        /// the program that was run, with its arguments, as for
+        /// `CouldNotExecuteCommand`
+        command: String,
         /// stdout output from the command
         stdout: String,
         /// stderr output from the command
@@ -365,25 +2405,183 @@ pub enum Error {
     UnknownPreReleaseTag(String),
     /// An error occurred in parsing a `LlvmVersion`.
     LlvmVersionError(LlvmVersionParseError),
+    /// `$RUSTC` could not be executed and looks like it contains arguments.
+    RustcContainsArguments(OsString),
+    /// The compiler is older than required.
+    VersionTooOld {
+        /// Version of the compiler
+        found: Version,
+        /// Minimum version that was required
+        required: Version,
+    },
+    /// An error occurred while writing a file.
+    CouldNotWriteFile(io::Error),
+    /// An error occurred while reading a file.
+    CouldNotReadFile(io::Error),
+    /// The output of `rustc -vV` was empty.
+    ///
+    /// This usually means a `$RUSTC_WRAPPER` didn't pass `-vV` on to rustc.
+    EmptyOutput,
+    /// The output doesn't start with the name of a known program, like `rustc`.
+    NotACompilerVersion(String),
+    /// The output of a command lacks the expected `rustc -vV` fields; holds
+    /// the start of the output.
+    ///
+    /// This can happen when `$RUSTC` points to a script that prints something
+    /// else.
+    UnexpectedOutput(String),
+    /// The JSON passed to `VersionMeta::from_json` is invalid; holds a
+    /// description of the problem.
+    InvalidJson(String),
+    /// A version requirement could not be parsed.
+    ReqParseError {
+        /// the requirement that was parsed
+        req: String,
+        /// the error from parsing the requirement
+        source: semver::Error,
+    },
+    /// The feature passed to `feature_accepted_for` isn't a plain identifier.
+    InvalidFeatureName(String),
+    /// An error with context added by `Error::context`.
+    ///
+    /// `code` and `help` are those of the wrapped error.
+    WithContext {
+        /// description of what was being done
+        context: String,
+        /// the wrapped error
+        source: Box<Error>,
+    },
+}
+
+impl Error {
+    /// Wraps the error with a description of what was being done, e.g.
+    /// `while detecting the toolchain for wasm32-unknown-unknown`.
+    ///
+    /// The context is prepended to the `Display` output, and the original
+    /// error is available as the `source`.
+    pub fn context<S: Into<String>>(self, context: S) -> Error {
+        WithContext {
+            context: context.into(),
+            source: Box::new(self),
+        }
+    }
+
+    /// Returns a short code identifying the kind of error.
+    ///
+    /// Unlike the `Display` output, these codes are stable and suitable for
+    /// matching on programmatically.
+    pub fn code(&self) -> &'static str {
+        match *self {
+            CouldNotExecuteCommand { .. } => "exec",
+            CommandError { .. } => "command",
+            Utf8Error(_) => "utf8",
+            UnexpectedVersionFormat => "format",
+            SemVerError(_) => "semver",
+            UnknownPreReleaseTag(_) => "pre_release_tag",
+            LlvmVersionError(_) => "llvm",
+            RustcContainsArguments(_) => "rustc_args",
+            VersionTooOld { .. } => "too_old",
+            CouldNotWriteFile(_) => "write",
+            CouldNotReadFile(_) => "read",
+            EmptyOutput => "empty",
+            NotACompilerVersion(_) => "not_compiler",
+            UnexpectedOutput(_) => "unexpected_output",
+            InvalidJson(_) => "json",
+            ReqParseError { .. } => "req_parse",
+            InvalidFeatureName(_) => "feature_name",
+            WithContext { ref source, .. } => source.code(),
+        }
+    }
+
+    /// Returns a hint for the user on how to resolve the error, if there is one.
+    ///
+    /// This is meant for tools that render diagnostics with separate help text.
+    pub fn help(&self) -> Option<&'static str> {
+        match *self {
+            CouldNotExecuteCommand { .. } => Some("is rustc installed and on PATH?"),
+            CommandError { .. } => Some("check that `$RUSTC` and `$RUSTC_WRAPPER` refer to rustc"),
+            Utf8Error(_) | UnexpectedVersionFormat => {
+                Some("check that `rustc -vV` prints the compiler version")
+            }
+            SemVerError(_) | UnknownPreReleaseTag(_) | LlvmVersionError(_) => None,
+            InvalidJson(_) => Some("pass JSON produced by `VersionMeta::to_json`"),
+            ReqParseError { .. } => Some("use a requirement like `>=1.70` or `^1.70.0`"),
+            InvalidFeatureName(_) => Some("pass the name of a feature, like `never_type`"),
+            WithContext { ref source, .. } => source.help(),
+            RustcContainsArguments(_) => {
+                Some("set `$RUSTC` to the path of the compiler only, or use `$RUSTC_WRAPPER`")
+            }
+            VersionTooOld { .. } => Some("update rustc, e.g. with `rustup update`"),
+            CouldNotWriteFile(_) => None,
+            CouldNotReadFile(_) => None,
+            EmptyOutput => Some("check that `$RUSTC_WRAPPER` passes `-vV` on to rustc"),
+            NotACompilerVersion(_) => Some("check that `$RUSTC` refers to rustc"),
+            UnexpectedOutput(_) => {
+                Some("check that `$RUSTC` passes `-vV` on to rustc and prints nothing else")
+            }
+        }
+    }
 }
 
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match *self {
-            CouldNotExecuteCommand(ref e) => write!(f, "could not execute command: {}", e),
+            CouldNotExecuteCommand {
+                ref command,
+                ref source,
+            } => write!(f, "could not execute command {}: {}", command, source),
             CommandError {
+                ref command,
                 ref stdout,
                 ref stderr,
             } => write!(
                 f,
-                "error from command -- stderr:\n\n{}\n\nstderr:\n\n{}",
-                stderr, stdout,
+                "error from command {} -- stderr:\n\n{}\n\nstderr:\n\n{}",
+                command, stderr, stdout,
             ),
             Utf8Error(_) => write!(f, "invalid UTF-8 output from `rustc -vV`"),
             UnexpectedVersionFormat => write!(f, "unexpected `rustc -vV` format"),
             SemVerError(ref e) => write!(f, "error parsing version: {}", e),
             UnknownPreReleaseTag(ref i) => write!(f, "unknown pre-release tag: {}", i),
             LlvmVersionError(ref e) => write!(f, "error parsing LLVM's version: {}", e),
+            RustcContainsArguments(ref rustc) => write!(
+                f,
+                "could not execute `$RUSTC` ({:?}); it is used as the path of the compiler \
+                 and cannot contain arguments",
+                rustc
+            ),
+            VersionTooOld {
+                ref found,
+                ref required,
+            } => write!(
+                f,
+                "rustc {} is too old, at least {} is required",
+                found, required
+            ),
+            CouldNotWriteFile(ref e) => write!(f, "could not write file: {}", e),
+            CouldNotReadFile(ref e) => write!(f, "could not read file: {}", e),
+            EmptyOutput => write!(
+                f,
+                "empty `rustc -vV` output; is rustc run through a wrapper that drops `-vV`?"
+            ),
+            NotACompilerVersion(ref line) => write!(
+                f,
+                "`rustc -vV` output doesn't start with a known program: {}",
+                line
+            ),
+            UnexpectedOutput(ref output) => {
+                write!(f, "unexpected `rustc -vV` output:\n\n{}", output)
+            }
+            InvalidJson(ref problem) => write!(f, "invalid version JSON: {}", problem),
+            ReqParseError {
+                ref req,
+                ref source,
+            } => write!(f, "error parsing version requirement `{}`: {}", req, source),
+            InvalidFeatureName(ref feature) => write!(f, "invalid feature name `{}`", feature),
+            WithContext {
+                ref context,
+                ref source,
+            } => write!(f, "{}: {}", context, source),
         }
     }
 }
@@ -391,13 +2589,24 @@ impl fmt::Display for Error {
 impl error::Error for Error {
     fn source(&self) -> Option<&(dyn error::Error + 'static)> {
         match *self {
-            CouldNotExecuteCommand(ref e) => Some(e),
+            CouldNotExecuteCommand { ref source, .. } => Some(source),
             CommandError { .. } => None,
             Utf8Error(ref e) => Some(e),
             UnexpectedVersionFormat => None,
             SemVerError(ref e) => Some(e),
             UnknownPreReleaseTag(_) => None,
             LlvmVersionError(ref e) => Some(e),
+            RustcContainsArguments(_) => None,
+            VersionTooOld { .. } => None,
+            CouldNotWriteFile(ref e) => Some(e),
+            CouldNotReadFile(ref e) => Some(e),
+            EmptyOutput => None,
+            NotACompilerVersion(_) => None,
+            UnexpectedOutput(_) => None,
+            InvalidJson(_) => None,
+            ReqParseError { ref source, .. } => Some(source),
+            InvalidFeatureName(_) => None,
+            WithContext { ref source, .. } => Some(&**source),
         }
     }
 }
@@ -1,10 +1,13 @@
 #![allow(clippy::match_like_matches_macro)]
 
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
 use std::process::Command;
+use std::{env, fs, io, str};
 
 use rustc_version::{
-    version, version_meta, version_meta_for, Channel, Error, LlvmVersion, LlvmVersionParseError,
-    Version, VersionMeta,
+    target_list_for, version, version_meta, version_meta_for, version_meta_for_lenient, Channel,
+    CompilerKind, Error, LlvmVersion, LlvmVersionParseError, Version, VersionKey, VersionMeta,
 };
 
 #[test]
@@ -12,7 +15,7 @@ fn rustc_error() {
     let mut cmd = Command::new("rustc");
     cmd.arg("--FOO");
     let stderr = match VersionMeta::for_command(cmd) {
-        Err(Error::CommandError { stdout: _, stderr }) => stderr,
+        Err(Error::CommandError { stderr, .. }) => stderr,
         _ => panic!("command error expected"),
     };
     assert_eq!(stderr, "error: Unrecognized option: \'FOO\'\n\n");
@@ -160,7 +163,11 @@ LLVM version: 3.9",
     );
     assert_eq!(
         version.llvm_version,
-        Some(LlvmVersion { major: 3, minor: 9 })
+        Some(LlvmVersion {
+            major: 3,
+            minor: 9,
+            patch: None
+        })
     );
 }
 
@@ -194,10 +201,26 @@ LLVM version: 11.0",
         Some(LlvmVersion {
             major: 11,
             minor: 0,
+            patch: None
         })
     );
 }
 
+/// Returns the metadata of the stable 1.47.0 release, for tests that don't
+/// exercise parsing.
+fn stable_1_47() -> VersionMeta {
+    version_meta_for(
+        "rustc 1.47.0 (18bf6b4f0 2020-10-07)
+binary: rustc
+commit-hash: 18bf6b4f01a6feaf7259ba7cdae58031af1b7b39
+commit-date: 2020-10-07
+host: powerpc64le-unknown-linux-gnu
+release: 1.47.0
+LLVM version: 11.0",
+    )
+    .unwrap()
+}
+
 #[test]
 fn parse_llvm_micro() {
     let version = version_meta_for(
@@ -226,7 +249,8 @@ LLVM version: 11.0.1",
         version.llvm_version,
         Some(LlvmVersion {
             major: 11,
-            minor: 0
+            minor: 0,
+            patch: Some(1)
         })
     );
 }
@@ -252,7 +276,11 @@ LLVM version: 7.0",
     assert_eq!(version.short_version_string, "rustc 1.41.1");
     assert_eq!(
         version.llvm_version,
-        Some(LlvmVersion { major: 7, minor: 0 })
+        Some(LlvmVersion {
+            major: 7,
+            minor: 0,
+            patch: None
+        })
     );
 }
 
@@ -280,6 +308,7 @@ LLVM version: 10.0",
         Some(LlvmVersion {
             major: 10,
             minor: 0,
+            patch: None
         })
     );
 }
@@ -315,7 +344,7 @@ fn parse_llvm_version_overflow() {
 fn parse_llvm_version_leading_zero_on_zero() {
     let res: Result<LlvmVersion, _> = "00".parse();
     assert!(match res {
-        Err(LlvmVersionParseError::ComponentMustNotHaveLeadingZeros) => true,
+        Err(LlvmVersionParseError::ComponentMustNotHaveLeadingZeros(_)) => true,
         _ => false,
     });
 }
@@ -324,7 +353,7 @@ fn parse_llvm_version_leading_zero_on_zero() {
 fn parse_llvm_version_leading_zero_on_nonzero() {
     let res: Result<LlvmVersion, _> = "01".parse();
     assert!(match res {
-        Err(LlvmVersionParseError::ComponentMustNotHaveLeadingZeros) => true,
+        Err(LlvmVersionParseError::ComponentMustNotHaveLeadingZeros(_)) => true,
         _ => false,
     });
 }
@@ -334,7 +363,7 @@ fn parse_llvm_version_4_components() {
     let res: Result<LlvmVersion, _> = "4.0.0.0".parse();
 
     assert!(match res {
-        Err(LlvmVersionParseError::TooManyComponents) => true,
+        Err(LlvmVersionParseError::TooManyComponents(_)) => true,
         _ => false,
     });
 }
@@ -344,7 +373,7 @@ fn parse_llvm_version_component_sign_plus() {
     let res: Result<LlvmVersion, _> = "1.+3".parse();
 
     assert!(match res {
-        Err(LlvmVersionParseError::ComponentMustNotHaveSign) => true,
+        Err(LlvmVersionParseError::ComponentMustNotHaveSign(_)) => true,
         _ => false,
     });
 }
@@ -354,7 +383,7 @@ fn parse_llvm_version_component_sign_minus() {
     let res: Result<LlvmVersion, _> = "1.-3".parse();
 
     assert!(match res {
-        Err(LlvmVersionParseError::ComponentMustNotHaveSign) => true,
+        Err(LlvmVersionParseError::ComponentMustNotHaveSign(_)) => true,
         _ => false,
     });
 }
@@ -364,7 +393,7 @@ fn parse_llvm_version_3() {
     let res: Result<LlvmVersion, _> = "3".parse();
 
     assert!(match res {
-        Err(LlvmVersionParseError::MinorVersionRequiredBefore4) => true,
+        Err(LlvmVersionParseError::MinorVersionRequiredBefore4(_)) => true,
         _ => false,
     });
 }
@@ -372,31 +401,66 @@ fn parse_llvm_version_3() {
 #[test]
 fn parse_llvm_version_5() {
     let v: LlvmVersion = "5".parse().unwrap();
-    assert_eq!(v, LlvmVersion { major: 5, minor: 0 });
+    assert_eq!(
+        v,
+        LlvmVersion {
+            major: 5,
+            minor: 0,
+            patch: None
+        }
+    );
 }
 
 #[test]
 fn parse_llvm_version_5_0() {
     let v: LlvmVersion = "5.0".parse().unwrap();
-    assert_eq!(v, LlvmVersion { major: 5, minor: 0 });
+    assert_eq!(
+        v,
+        LlvmVersion {
+            major: 5,
+            minor: 0,
+            patch: None
+        }
+    );
 }
 
 #[test]
 fn parse_llvm_version_4_0() {
     let v: LlvmVersion = "4.0".parse().unwrap();
-    assert_eq!(v, LlvmVersion { major: 4, minor: 0 });
+    assert_eq!(
+        v,
+        LlvmVersion {
+            major: 4,
+            minor: 0,
+            patch: None
+        }
+    );
 }
 
 #[test]
 fn parse_llvm_version_3_0() {
     let v: LlvmVersion = "3.0".parse().unwrap();
-    assert_eq!(v, LlvmVersion { major: 3, minor: 0 });
+    assert_eq!(
+        v,
+        LlvmVersion {
+            major: 3,
+            minor: 0,
+            patch: None
+        }
+    );
 }
 
 #[test]
 fn parse_llvm_version_3_9() {
     let v: LlvmVersion = "3.9".parse().unwrap();
-    assert_eq!(v, LlvmVersion { major: 3, minor: 9 });
+    assert_eq!(
+        v,
+        LlvmVersion {
+            major: 3,
+            minor: 9,
+            patch: None
+        }
+    );
 }
 
 #[test]
@@ -406,7 +470,8 @@ fn parse_llvm_version_11_0() {
         v,
         LlvmVersion {
             major: 11,
-            minor: 0
+            minor: 0,
+            patch: None
         }
     );
 }
@@ -418,7 +483,8 @@ fn parse_llvm_version_11() {
         v,
         LlvmVersion {
             major: 11,
-            minor: 0
+            minor: 0,
+            patch: None
         }
     );
 }
@@ -426,7 +492,2464 @@ fn parse_llvm_version_11() {
 #[test]
 fn test_llvm_version_comparison() {
     // check that field order is correct
-    assert!(LlvmVersion { major: 3, minor: 9 } < LlvmVersion { major: 4, minor: 0 });
+    assert!(
+        LlvmVersion {
+            major: 3,
+            minor: 9,
+            patch: None
+        } < LlvmVersion {
+            major: 4,
+            minor: 0,
+            patch: None
+        }
+    );
+}
+
+#[test]
+fn parse_rc() {
+    let version = version_meta_for(
+        "rustc 1.70.0-rc.1 (90c541806 2023-05-31)
+binary: rustc
+commit-hash: 90c541806f23a127002de5b4038be731ba1458ca
+commit-date: 2023-05-31
+host: x86_64-unknown-linux-gnu
+release: 1.70.0-rc.1
+LLVM version: 16.0",
+    )
+    .unwrap();
+
+    assert_eq!(version.semver, Version::parse("1.70.0-rc.1").unwrap());
+    assert_eq!(version.channel, Channel::Beta);
+}
+
+#[test]
+fn version_meta_as_ref_version() {
+    fn major<V: AsRef<Version>>(v: V) -> u64 {
+        v.as_ref().major
+    }
+
+    let version = version_meta_for(
+        "rustc 1.3.0
+binary: rustc
+commit-hash: unknown
+commit-date: unknown
+host: x86_64-unknown-linux-gnu
+release: 1.3.0",
+    )
+    .unwrap();
+
+    assert_eq!(major(&version), 1);
+    assert_eq!(version.as_ref(), &Version::parse("1.3.0").unwrap());
+}
+
+#[test]
+fn is_official_release() {
+    let stable = stable_1_47();
+    assert!(stable.is_official_release());
+
+    let dev = version_meta_for(
+        "rustc 1.52.0-dev
+binary: rustc
+commit-hash: unknown
+commit-date: unknown
+host: x86_64-unknown-linux-gnu
+release: 1.52.0-dev
+LLVM version: 11.0",
+    )
+    .unwrap();
+    assert!(!dev.is_official_release());
+
+    let distro = version_meta_for(
+        "rustc 1.41.1
+binary: rustc
+commit-hash: unknown
+commit-date: unknown
+host: powerpc64le-unknown-linux-gnu
+release: 1.41.1
+LLVM version: 7.0",
+    )
+    .unwrap();
+    assert!(!distro.is_official_release());
+}
+
+#[test]
+fn for_command_with_reused_closure() {
+    let mut calls = 0;
+    let mut make_cmd = || {
+        calls += 1;
+        let mut cmd = Command::new("rustc");
+        cmd.env("RUSTC_VERSION_TEST", "1");
+        cmd
+    };
+
+    let first = VersionMeta::for_command_with(&mut make_cmd).unwrap();
+    let second = VersionMeta::for_command_with(&mut make_cmd).unwrap();
+    assert_eq!(first, second);
+    assert_eq!(calls, 2);
+}
+
+#[test]
+fn llvm_version_is_at_least() {
+    let v3_9 = LlvmVersion {
+        major: 3,
+        minor: 9,
+        patch: None,
+    };
+    let v4_0 = LlvmVersion {
+        major: 4,
+        minor: 0,
+        patch: None,
+    };
+
+    assert!(v3_9.is_at_least(3, 9));
+    assert!(!v3_9.is_at_least(4, 0));
+    assert!(v3_9.is_before(4, 0));
+    assert!(!v3_9.is_before(3, 9));
+
+    assert!(v4_0.is_at_least(3, 9));
+    assert!(v4_0.is_at_least(4, 0));
+    assert!(!v4_0.is_at_least(4, 1));
+    assert!(v4_0.is_before(5, 0));
+    assert!(!v4_0.is_before(4, 0));
+}
+
+#[test]
+fn error_codes() {
+    let invalid_utf8 = vec![0xff];
+    let errors = vec![
+        (
+            Error::CouldNotExecuteCommand {
+                command: "\"rustc\" \"-vV\"".into(),
+                source: io::Error::new(io::ErrorKind::NotFound, "rustc"),
+            },
+            "exec",
+        ),
+        (
+            Error::CommandError {
+                command: "\"rustc\" \"-vV\"".into(),
+                stdout: String::new(),
+                stderr: String::new(),
+            },
+            "command",
+        ),
+        (
+            Error::Utf8Error(str::from_utf8(&invalid_utf8).unwrap_err()),
+            "utf8",
+        ),
+        (Error::UnexpectedVersionFormat, "format"),
+        (
+            Error::SemVerError(Version::parse("one").unwrap_err()),
+            "semver",
+        ),
+        (
+            Error::UnknownPreReleaseTag("alpha".into()),
+            "pre_release_tag",
+        ),
+        (
+            Error::LlvmVersionError("".parse::<LlvmVersion>().unwrap_err()),
+            "llvm",
+        ),
+        (
+            Error::RustcContainsArguments("rustc --some-flag".into()),
+            "rustc_args",
+        ),
+        (
+            Error::VersionTooOld {
+                found: Version::new(1, 0, 0),
+                required: Version::new(1, 1, 0),
+            },
+            "too_old",
+        ),
+        (
+            Error::CouldNotWriteFile(io::Error::new(io::ErrorKind::Other, "full")),
+            "write",
+        ),
+        (
+            Error::CouldNotReadFile(io::Error::new(io::ErrorKind::NotFound, "file")),
+            "read",
+        ),
+        (Error::EmptyOutput, "empty"),
+        (
+            Error::NotACompilerVersion("hello world".into()),
+            "not_compiler",
+        ),
+        (
+            Error::UnexpectedOutput("rustc 1.3.0".into()),
+            "unexpected_output",
+        ),
+        (Error::InvalidJson("expected `{`".into()), "json"),
+        (
+            Error::ReqParseError {
+                req: "foo".into(),
+                source: semver::VersionReq::parse("foo").unwrap_err(),
+            },
+            "req_parse",
+        ),
+        (
+            Error::InvalidFeatureName("../feature".into()),
+            "feature_name",
+        ),
+    ];
+
+    let mut codes = HashSet::new();
+    for (error, code) in &errors {
+        assert_eq!(error.code(), *code);
+        assert!(codes.insert(error.code()), "duplicate code {}", code);
+    }
+}
+
+#[test]
+fn parse_miri() {
+    let version = version_meta_for(
+        "miri 0.1.0 (c4c4b8c 2024-04-08)
+rustc 1.79.0-nightly (c4c4b8c0a 2024-04-08)
+binary: rustc
+commit-hash: c4c4b8c0ab3ac42ed67e2d64c4ccd67b21d1c2b1
+commit-date: 2024-04-08
+host: x86_64-unknown-linux-gnu
+release: 1.79.0-nightly
+LLVM version: 18.1.3",
+    )
+    .unwrap();
+
+    assert_eq!(version.semver, Version::parse("1.79.0-nightly").unwrap());
+    assert_eq!(version.channel, Channel::Nightly);
+    assert_eq!(version.host, "x86_64-unknown-linux-gnu");
+    assert_eq!(
+        version.short_version_string,
+        "rustc 1.79.0-nightly (c4c4b8c0a 2024-04-08)"
+    );
+    assert_eq!(
+        version.llvm_version,
+        Some(LlvmVersion {
+            major: 18,
+            minor: 1,
+            patch: Some(3)
+        })
+    );
+}
+
+#[test]
+fn version_meta_overrides() {
+    let base = stable_1_47();
+
+    let version = base
+        .clone()
+        .with_semver(Version::parse("1.48.0-nightly").unwrap())
+        .with_channel(Channel::Nightly)
+        .with_host("x86_64-unknown-linux-gnu");
+
+    assert_eq!(version.semver, Version::parse("1.48.0-nightly").unwrap());
+    assert_eq!(version.channel, Channel::Nightly);
+    assert_eq!(version.host, "x86_64-unknown-linux-gnu");
+    assert_eq!(version.commit_hash, base.commit_hash);
+    assert_eq!(version.commit_date, base.commit_date);
+    assert_eq!(version.build_date, base.build_date);
+    assert_eq!(version.short_version_string, base.short_version_string);
+    assert_eq!(version.llvm_version, base.llvm_version);
+
+    let version = base
+        .clone()
+        .with_commit_hash(None)
+        .with_commit_date(None)
+        .with_build_date(Some("2020-10-08".into()))
+        .with_short_version_string("rustc 1.47.0")
+        .with_llvm_version(None);
+
+    assert_eq!(version.commit_hash, None);
+    assert_eq!(version.commit_date, None);
+    assert_eq!(version.build_date, Some("2020-10-08".into()));
+    assert_eq!(version.short_version_string, "rustc 1.47.0");
+    assert_eq!(version.llvm_version, None);
+    assert_eq!(version.semver, base.semver);
+    assert_eq!(version.channel, base.channel);
+    assert_eq!(version.host, base.host);
+}
+
+#[test]
+fn is_distro_build() {
+    let debian = version_meta_for(
+        "rustc 1.41.1
+binary: rustc
+commit-hash: unknown
+commit-date: unknown
+host: powerpc64le-unknown-linux-gnu
+release: 1.41.1
+LLVM version: 7.0",
+    )
+    .unwrap();
+    assert!(debian.is_distro_build());
+
+    let official = debian
+        .clone()
+        .with_commit_hash(Some("18bf6b4f01a6feaf7259ba7cdae58031af1b7b39".into()))
+        .with_commit_date(Some("2020-10-07".into()));
+    assert!(!official.is_distro_build());
+
+    let without_llvm = debian.clone().with_llvm_version(None);
+    assert!(!without_llvm.is_distro_build());
+
+    let nightly = debian.with_channel(Channel::Nightly);
+    assert!(!nightly.is_distro_build());
+}
+
+#[test]
+fn compare_to() {
+    let stable = stable_1_47();
+    let v = |s| Version::parse(s).unwrap();
+
+    assert_eq!(stable.compare_to(&v("1.46.0"), false), Ordering::Greater);
+    assert_eq!(stable.compare_to(&v("1.47.0"), false), Ordering::Equal);
+    assert_eq!(stable.compare_to(&v("1.48.0"), false), Ordering::Less);
+    assert_eq!(stable.compare_to(&v("1.47.0"), true), Ordering::Equal);
+
+    let nightly = stable
+        .with_semver(v("1.47.0-nightly"))
+        .with_channel(Channel::Nightly);
+
+    assert_eq!(nightly.compare_to(&v("1.47.0"), false), Ordering::Less);
+    assert_eq!(nightly.compare_to(&v("1.47.0"), true), Ordering::Equal);
+    assert_eq!(nightly.compare_to(&v("1.46.0"), true), Ordering::Greater);
+    assert_eq!(nightly.compare_to(&v("1.48.0"), true), Ordering::Less);
+}
+
+#[test]
+fn llvm_version_or() {
+    let version = version_meta_for(
+        "rustc 1.0.0 (a59de37e9 2015-05-13) (built 2015-05-14)
+binary: rustc
+commit-hash: a59de37e99060162a2674e3ff45409ac73595c0e
+commit-date: 2015-05-13
+build-date: 2015-05-14
+host: x86_64-unknown-linux-gnu
+release: 1.0.0",
+    )
+    .unwrap();
+    assert_eq!(
+        version.llvm_version_or(LlvmVersion {
+            major: 3,
+            minor: 6,
+            patch: None
+        }),
+        LlvmVersion {
+            major: 3,
+            minor: 6,
+            patch: None
+        }
+    );
+
+    let version = version.with_llvm_version(Some(LlvmVersion {
+        major: 7,
+        minor: 0,
+        patch: None,
+    }));
+    assert_eq!(
+        version.llvm_version_or(LlvmVersion {
+            major: 3,
+            minor: 6,
+            patch: None
+        }),
+        LlvmVersion {
+            major: 7,
+            minor: 0,
+            patch: None
+        }
+    );
+}
+
+#[test]
+fn host_is_known_target() {
+    let version = version_meta_for(
+        "rustc 1.46.0
+binary: rustc
+commit-hash: unknown
+commit-date: unknown
+host: aarch64-linux-android
+release: 1.46.0
+LLVM version: 10.0",
+    )
+    .unwrap();
+    assert!(version.host_is_known_target());
+    assert!(version
+        .clone()
+        .with_host("x86_64-unknown-linux-gnu")
+        .host_is_known_target());
+    assert!(!version
+        .with_host("x86_64-unknown-linux-gnux")
+        .host_is_known_target());
+}
+
+#[test]
+fn target_list() {
+    let targets = rustc_version::target_list().unwrap();
+    assert!(targets.len() > 1);
+    if cfg!(all(
+        target_arch = "x86_64",
+        target_os = "linux",
+        target_env = "gnu"
+    )) {
+        assert!(targets.iter().any(|t| t == "x86_64-unknown-linux-gnu"));
+    }
+
+    let targets = target_list_for(Command::new("rustc")).unwrap();
+    assert!(targets.iter().any(|t| t == "wasm32-unknown-unknown"));
+}
+
+#[test]
+fn beta_number() {
+    let beta = version_meta_for(
+        "rustc 1.70.0-beta.3 (0b4e5d9f5 2023-05-13)
+binary: rustc
+commit-hash: 0b4e5d9f53a2b64fb8cf4ebc8bdc1a5b5ec5a6d3
+commit-date: 2023-05-13
+host: x86_64-unknown-linux-gnu
+release: 1.70.0-beta.3
+LLVM version: 16.0.2",
+    )
+    .unwrap();
+    assert_eq!(beta.channel, Channel::Beta);
+    assert_eq!(beta.beta_number(), Some(3));
+
+    let stable = beta
+        .clone()
+        .with_semver(Version::parse("1.70.0").unwrap())
+        .with_channel(Channel::Stable);
+    assert_eq!(stable.beta_number(), None);
+
+    let unnumbered = beta.with_semver(Version::parse("1.5.0-beta").unwrap());
+    assert_eq!(unnumbered.beta_number(), None);
+}
+
+#[test]
+fn version_meta_with_env() {
+    let vars = || {
+        env::vars_os()
+            .filter(|(key, _)| key != "RUSTC" && key != "RUSTC_WRAPPER")
+            .collect::<Vec<_>>()
+    };
+
+    let mut custom = vars();
+    custom.push(("RUSTC".into(), "rustc".into()));
+    let version = rustc_version::version_meta_with_env(custom).unwrap();
+    assert!(version.semver.major >= 1);
+
+    let mut missing = vars();
+    missing.push(("RUSTC".into(), "rustc-version-does-not-exist".into()));
+    match rustc_version::version_meta_with_env(missing) {
+        Err(Error::CouldNotExecuteCommand { .. }) => {}
+        _ => panic!("could not execute command error expected"),
+    }
+
+    // the environment is replaced, so without `PATH` only the default search
+    // path is searched, where rustup doesn't install rustc
+    let default_path = ["/bin/rustc", "/usr/bin/rustc"];
+    if cfg!(unix)
+        && !default_path
+            .iter()
+            .any(|p| std::path::Path::new(p).exists())
+    {
+        let bare = vec![("RUSTC".into(), "rustc".into())];
+        match rustc_version::version_meta_with_env(bare) {
+            Err(Error::CouldNotExecuteCommand { .. }) => {}
+            _ => panic!("could not execute command error expected"),
+        }
+    }
+}
+
+#[test]
+fn version_key_lookup() {
+    let version = stable_1_47();
+
+    let mut map = HashMap::new();
+    map.insert(VersionKey::from(version.clone()), "stable");
+    assert_eq!(map.get(&Version::parse("1.47.0").unwrap()), Some(&"stable"));
+    assert_eq!(map.get(&Version::parse("1.48.0").unwrap()), None);
+
+    let rebuilt = version.with_commit_hash(None);
+    assert_eq!(map.get(&VersionKey(rebuilt)), Some(&"stable"));
+}
+
+#[test]
+fn parse_rust_birthday() {
+    let version = version_meta_for(
+        "rustc 1.0.0-nightly (b0746ff19 2015-03-05) (built 2015-03-06)
+binary: rustc
+commit-hash: b0746ff19b3bc204215f04bf6d9f5d2b0f6f1e9c
+commit-date: 2015-03-05
+rust-birthday: 2015-03-06
+host: x86_64-unknown-linux-gnu
+release: 1.0.0-nightly",
+    )
+    .unwrap();
+
+    assert_eq!(version.semver, Version::parse("1.0.0-nightly").unwrap());
+    assert_eq!(version.commit_date, Some("2015-03-05".into()));
+    assert_eq!(version.build_date, Some("2015-03-06".into()));
+    assert_eq!(version.channel, Channel::Nightly);
+}
+
+#[test]
+fn require_min() {
+    let version = stable_1_47();
+
+    assert!(version
+        .require_min(Version::parse("1.47.0").unwrap())
+        .is_ok());
+    assert!(version
+        .require_min(Version::parse("1.31.0").unwrap())
+        .is_ok());
+
+    let err = version
+        .require_min(Version::parse("1.56.0").unwrap())
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "rustc 1.47.0 is too old, at least 1.56.0 is required"
+    );
+    match err {
+        Error::VersionTooOld { found, required } => {
+            assert_eq!(found, Version::parse("1.47.0").unwrap());
+            assert_eq!(required, Version::parse("1.56.0").unwrap());
+        }
+        _ => panic!("version too old error expected"),
+    }
+}
+
+#[test]
+fn error_help() {
+    let err = VersionMeta::for_command(Command::new("rustc-version-does-not-exist")).unwrap_err();
+    assert_eq!(err.help(), Some("is rustc installed and on PATH?"));
+
+    let err = Error::UnknownPreReleaseTag("alpha".into());
+    assert_eq!(err.help(), None);
+}
+
+#[test]
+fn parse_lenient_leading_noise() {
+    let output = "warning: unused config key `build.foo` in `/home/user/.cargo/config.toml`
+rustc 1.47.0 (18bf6b4f0 2020-10-07)
+binary: rustc
+commit-hash: 18bf6b4f01a6feaf7259ba7cdae58031af1b7b39
+commit-date: 2020-10-07
+host: powerpc64le-unknown-linux-gnu
+release: 1.47.0
+LLVM version: 11.0";
+
+    let version = version_meta_for_lenient(output).unwrap();
+    assert_eq!(version.semver, Version::parse("1.47.0").unwrap());
+    assert_eq!(
+        version.short_version_string,
+        "rustc 1.47.0 (18bf6b4f0 2020-10-07)"
+    );
+    assert_eq!(version.host, "powerpc64le-unknown-linux-gnu");
+}
+
+#[test]
+fn llvm_version_major_only() {
+    let v = LlvmVersion::from_major(11);
+    assert_eq!(
+        v,
+        LlvmVersion {
+            major: 11,
+            minor: 0,
+            patch: None
+        }
+    );
+    assert_eq!(v.to_string(), "11.0");
+    assert_eq!(v.to_major_string(), "11");
+    assert_eq!(
+        LlvmVersion {
+            major: 3,
+            minor: 9,
+            patch: None
+        }
+        .to_major_string(),
+        "3.9"
+    );
+}
+
+#[test]
+fn at_least_minor() {
+    let version = version_meta_for(
+        "rustc 1.56.0 (09c42c458 2021-10-18)
+binary: rustc
+commit-hash: 09c42c45858d5f3aedfa670698275303a3d19afa
+commit-date: 2021-10-18
+host: x86_64-unknown-linux-gnu
+release: 1.56.0
+LLVM version: 13.0.0",
+    )
+    .unwrap();
+    assert!(version.at_least_minor(1, 56));
+    assert!(version.at_least_minor(1, 55));
+    assert!(!version.at_least_minor(1, 57));
+    assert!(!version.at_least_minor(2, 0));
+
+    let patched = version
+        .clone()
+        .with_semver(Version::parse("1.56.5").unwrap());
+    assert!(patched.at_least_minor(1, 56));
+    assert!(!patched.at_least_minor(1, 57));
+
+    let older = version.with_semver(Version::parse("1.55.9").unwrap());
+    assert!(!older.at_least_minor(1, 56));
+}
+
+#[test]
+fn is_bootstrap_build() {
+    let dev = version_meta_for(
+        "rustc 1.52.0-dev
+binary: rustc
+commit-hash: unknown
+commit-date: unknown
+host: x86_64-unknown-linux-gnu
+release: 1.52.0-dev
+LLVM version: 11.0",
+    )
+    .unwrap();
+    assert_eq!(dev.channel, Channel::Dev);
+    assert!(dev.is_bootstrap_build());
+
+    let with_commit = dev
+        .clone()
+        .with_commit_hash(Some("4253153db205251f72ea4493687a31e04a2a8ca0".into()));
+    assert!(!with_commit.is_bootstrap_build());
+
+    let distro = dev
+        .with_semver(Version::parse("1.52.0").unwrap())
+        .with_channel(Channel::Stable);
+    assert!(!distro.is_bootstrap_build());
+}
+
+#[test]
+fn channel_from_toolchain_name() {
+    use rustc_version::channel_from_toolchain_name;
+
+    assert_eq!(
+        channel_from_toolchain_name("stable-x86_64-unknown-linux-gnu"),
+        Some(Channel::Stable)
+    );
+    assert_eq!(channel_from_toolchain_name("stable"), Some(Channel::Stable));
+    assert_eq!(
+        channel_from_toolchain_name("beta-aarch64-apple-darwin"),
+        Some(Channel::Beta)
+    );
+    assert_eq!(
+        channel_from_toolchain_name("nightly-2023-06-01-x86_64-unknown-linux-gnu"),
+        Some(Channel::Nightly)
+    );
+    assert_eq!(
+        channel_from_toolchain_name("1.70.0-x86_64-pc-windows-msvc"),
+        Some(Channel::Stable)
+    );
+    assert_eq!(channel_from_toolchain_name("1.70"), Some(Channel::Stable));
+    assert_eq!(
+        channel_from_toolchain_name("1.70-x86_64-unknown-linux-gnu"),
+        Some(Channel::Stable)
+    );
+    assert_eq!(channel_from_toolchain_name("stage1"), None);
+    assert_eq!(channel_from_toolchain_name(""), None);
+}
+
+#[test]
+fn nightly_date() {
+    let nightly = version_meta_for(
+        "rustc 1.16.0-nightly (5d994d8b7 2017-01-05)
+binary: rustc
+commit-hash: 5d994d8b7e482e87467d4a521911477bd8284ce3
+commit-date: 2017-01-05
+host: x86_64-unknown-linux-gnu
+release: 1.16.0-nightly
+LLVM version: 3.9",
+    )
+    .unwrap();
+    assert_eq!(nightly.nightly_date(), Some("2017-01-05"));
+
+    let stable = stable_1_47();
+    assert_eq!(stable.nightly_date(), None);
+}
+
+#[test]
+fn write_version_constants() {
+    let dir = env::temp_dir().join("rustc_version_constants_test");
+    fs::create_dir_all(&dir).unwrap();
+    let out = dir.join("rustc_version.rs");
+    rustc_version::write_version_constants(&out).unwrap();
+
+    let src = fs::read_to_string(&out).unwrap();
+    let semver = format!("pub const RUSTC_SEMVER: &str = \"{}\";", version().unwrap());
+    assert!(src.contains(&semver));
+    assert!(src.contains("pub const RUSTC_CHANNEL: &str = \""));
+    assert!(src.contains("pub const RUSTC_HOST: &str = \""));
+    assert!(src.contains("pub const RUSTC_COMMIT_HASH: Option<&str> = "));
+    assert!(src.contains("pub const RUSTC_COMMIT_DATE: Option<&str> = "));
+    assert!(src.contains("pub const RUSTC_LLVM_VERSION: Option<&str> = "));
+
+    let status = Command::new("rustc")
+        .args(&["--crate-type", "lib", "--emit", "metadata", "--out-dir"])
+        .arg(&dir)
+        .arg(&out)
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+#[test]
+fn parse_empty() {
+    for output in &["", "\n", "  \n\n"] {
+        match version_meta_for(output) {
+            Err(Error::EmptyOutput) => {}
+            _ => panic!("empty output error expected"),
+        }
+    }
+}
+
+#[test]
+fn channel_eq_str() {
+    assert_eq!(Channel::Nightly.as_str(), "nightly");
+    assert!(Channel::Nightly == "nightly");
+    assert!(Channel::Stable == *"stable");
+    assert!(Channel::Beta != "stable");
+    assert!(Channel::Dev != "Dev");
+
+    let version = version_meta_for(
+        "rustc 1.16.0-nightly (5d994d8b7 2017-01-05)
+binary: rustc
+commit-hash: 5d994d8b7e482e87467d4a521911477bd8284ce3
+commit-date: 2017-01-05
+host: x86_64-unknown-linux-gnu
+release: 1.16.0-nightly
+LLVM version: 3.9",
+    )
+    .unwrap();
+    assert!(version.channel == "nightly");
+    assert!(version.channel != "beta");
+}
+
+#[test]
+fn oldest_supported_rustc() {
+    assert_eq!(
+        rustc_version::oldest_supported_rustc(),
+        Version::parse("1.0.0").unwrap()
+    );
+}
+
+#[test]
+fn llvm_supports_target() {
+    let version = stable_1_47();
+    assert_eq!(
+        version.llvm_supports_target("wasm32-unknown-unknown"),
+        Some(true)
+    );
+    assert_eq!(
+        version.llvm_supports_target("aarch64-apple-darwin"),
+        Some(true)
+    );
+    assert_eq!(
+        version.llvm_supports_target("loongarch64-unknown-linux-gnu"),
+        Some(false)
+    );
+    assert_eq!(
+        version.llvm_supports_target("x86_64-unknown-linux-gnu"),
+        None
+    );
+
+    let version = version.with_llvm_version(None);
+    assert_eq!(version.llvm_supports_target("wasm32-unknown-unknown"), None);
+}
+
+#[test]
+fn parse_multiple_llvm_versions() {
+    let version = version_meta_for(
+        "rustc 1.80.0-dev
+binary: rustc
+commit-hash: unknown
+commit-date: unknown
+host: x86_64-unknown-linux-gnu
+release: 1.80.0-dev
+LLVM version: 18.1.7
+LLVM version: 17.0",
+    )
+    .unwrap();
+
+    assert_eq!(
+        version.llvm_version,
+        Some(LlvmVersion {
+            major: 18,
+            minor: 1,
+            patch: Some(7)
+        })
+    );
+    assert_eq!(
+        version.all_llvm_versions(),
+        &[
+            LlvmVersion {
+                major: 18,
+                minor: 1,
+                patch: Some(7)
+            },
+            LlvmVersion {
+                major: 17,
+                minor: 0,
+                patch: None
+            },
+        ]
+    );
+
+    let version = version.with_llvm_version(None);
+    assert!(version.all_llvm_versions().is_empty());
+}
+
+#[test]
+fn parse_lossy_unknown_channel() {
+    let output = "rustc 1.80.0-experimental
+binary: rustc
+commit-hash: unknown
+commit-date: unknown
+host: x86_64-unknown-linux-gnu
+release: 1.80.0-experimental
+LLVM version: 18.1";
+
+    match version_meta_for(output) {
+        Err(Error::UnknownPreReleaseTag(ref tag)) => assert_eq!(tag, "experimental"),
+        _ => panic!("unknown pre-release tag error expected"),
+    }
+
+    let version = rustc_version::version_meta_for_lossy(output).unwrap();
+    assert_eq!(version.channel, Channel::Dev);
+    assert_eq!(version.semver.pre.as_str(), "experimental");
+}
+
+#[test]
+fn target_spec_json() {
+    let mut cmd = Command::new("rustc");
+    cmd.env_remove("RUSTC_BOOTSTRAP");
+    let res = rustc_version::target_spec_json_for(cmd, "x86_64-unknown-linux-gnu");
+
+    match VersionMeta::for_command(Command::new("rustc"))
+        .unwrap()
+        .channel
+    {
+        Channel::Nightly | Channel::Dev => assert!(res.unwrap().contains("\"llvm-target\"")),
+        Channel::Beta | Channel::Stable => match res {
+            Err(Error::CommandError { .. }) => {}
+            _ => panic!("command error expected"),
+        },
+    }
+}
+
+#[test]
+fn rerun_if_changed_directives() {
+    assert_eq!(
+        rustc_version::rerun_if_changed_directives(),
+        &[
+            "cargo:rerun-if-env-changed=RUSTC",
+            "cargo:rerun-if-env-changed=RUSTC_WRAPPER",
+        ]
+    );
+}
+
+#[test]
+fn parse_trailing_whitespace() {
+    let version = version_meta_for(concat!(
+        "rustc 1.47.0 (18bf6b4f0 2020-10-07)\n",
+        "binary: rustc\n",
+        "commit-hash: 18bf6b4f01a6feaf7259ba7cdae58031af1b7b39\n",
+        "commit-date: 2020-10-07\n",
+        "host: powerpc64le-unknown-linux-gnu  \t\n",
+        "release: 1.47.0   \n",
+        "LLVM version: 11.0 ",
+    ))
+    .unwrap();
+
+    assert_eq!(version.host, "powerpc64le-unknown-linux-gnu");
+    assert_eq!(version.semver, Version::parse("1.47.0").unwrap());
+    assert_eq!(
+        version.llvm_version,
+        Some(LlvmVersion {
+            major: 11,
+            minor: 0,
+            patch: None
+        })
+    );
+}
+
+#[test]
+fn channel_stability_at_least() {
+    let version = stable_1_47();
+
+    let by_stability = [
+        Channel::Dev,
+        Channel::Nightly,
+        Channel::Beta,
+        Channel::Stable,
+    ];
+    for (i, &channel) in by_stability.iter().enumerate() {
+        let version = version.clone().with_channel(channel);
+        for (j, &min) in by_stability.iter().enumerate() {
+            assert_eq!(
+                version.channel_stability_at_least(min),
+                i >= j,
+                "{:?} at least {:?}",
+                channel,
+                min
+            );
+        }
+    }
+}
+
+#[test]
+fn llvm_version_precision() {
+    let output = |llvm: &str| {
+        format!(
+            "rustc 1.51.0-nightly (4253153db 2021-01-17)
+binary: rustc
+commit-hash: 4253153db205251f72ea4493687a31e04a2a8ca0
+commit-date: 2021-01-17
+host: x86_64-pc-windows-msvc
+release: 1.51.0-nightly
+LLVM version: {}",
+            llvm
+        )
+    };
+
+    let precision = |llvm| {
+        version_meta_for(&output(llvm))
+            .unwrap()
+            .llvm_version_precision()
+    };
+    assert_eq!(precision("11"), Some(1));
+    assert_eq!(precision("11.0"), Some(2));
+    assert_eq!(precision("11.0.1"), Some(3));
+
+    // how the version was written doesn't make metas unequal
+    let short = version_meta_for(&output("11")).unwrap();
+    assert_eq!(short, version_meta_for(&output("11.0")).unwrap());
+
+    // the precision doesn't outlive a change of the public field
+    let mut changed = short.clone();
+    let llvm_12 = LlvmVersion {
+        major: 12,
+        minor: 0,
+        patch: Some(1),
+    };
+    changed.llvm_version = Some(llvm_12.clone());
+    assert_eq!(changed.llvm_version_precision(), Some(3));
+    assert_eq!(changed.all_llvm_versions(), &[llvm_12]);
+    assert!(changed
+        .to_verbose_string()
+        .contains("LLVM version: 12.0.1\n"));
+    assert!(short.to_verbose_string().contains("LLVM version: 11\n"));
+
+    let version = version_meta_for(
+        "rustc 1.3.0
+binary: rustc
+commit-hash: unknown
+commit-date: unknown
+host: x86_64-unknown-linux-gnu
+release: 1.3.0",
+    )
+    .unwrap();
+    assert_eq!(version.llvm_version_precision(), None);
+}
+
+#[test]
+fn for_command_raw() {
+    let mut cmd = Command::new("rustc");
+    cmd.arg("-vV");
+    let version = VersionMeta::for_command_raw(cmd).unwrap();
+    assert!(version.semver.major >= 1);
+
+    let mut cmd = Command::new("rustc");
+    cmd.arg("--version");
+    assert!(VersionMeta::for_command_raw(cmd).is_err());
+}
+
+#[test]
+fn host_platform_predicates() {
+    let version = version_meta_for(
+        "rustc 1.46.0
+binary: rustc
+commit-hash: unknown
+commit-date: unknown
+host: aarch64-linux-android
+release: 1.46.0
+LLVM version: 10.0",
+    )
+    .unwrap();
+    let host = |host: &str| version.clone().with_host(host);
+
+    let android = host("aarch64-linux-android");
+    assert!(android.host_is_android());
+    assert!(!android.host_is_windows());
+    assert!(!android.host_is_apple());
+    assert!(!android.host_is_musl());
+    assert!(!android.host_is_msvc());
+
+    assert!(host("armv7-linux-androideabi").host_is_android());
+
+    let msvc = host("x86_64-pc-windows-msvc");
+    assert!(msvc.host_is_windows());
+    assert!(msvc.host_is_msvc());
+    assert!(!msvc.host_is_android());
+
+    let gnu = host("x86_64-pc-windows-gnu");
+    assert!(gnu.host_is_windows());
+    assert!(!gnu.host_is_msvc());
+
+    assert!(host("aarch64-apple-darwin").host_is_apple());
+    assert!(host("x86_64-unknown-linux-musl").host_is_musl());
+    assert!(host("armv7-unknown-linux-musleabihf").host_is_musl());
+
+    let linux = host("powerpc64le-unknown-linux-gnu");
+    assert!(!linux.host_is_windows());
+    assert!(!linux.host_is_apple());
+    assert!(!linux.host_is_musl());
+    assert!(!linux.host_is_msvc());
+    assert!(!linux.host_is_android());
+}
+
+#[test]
+fn version_meta_for_path_with_args() {
+    let out = Command::new("rustc")
+        .args(&["--print", "sysroot"])
+        .output()
+        .unwrap();
+    let sysroot = String::from_utf8(out.stdout).unwrap();
+
+    let version =
+        rustc_version::version_meta_for_path_with_args("rustc", &["--sysroot", sysroot.trim()])
+            .unwrap();
+    assert_eq!(
+        version,
+        VersionMeta::for_command(Command::new("rustc")).unwrap()
+    );
+
+    let res = rustc_version::version_meta_for_path_with_args("rustc", &["--FOO"]);
+    match res {
+        Err(Error::CommandError { .. }) => {}
+        _ => panic!("command error expected"),
+    }
+}
+
+#[test]
+fn eq_ignoring_llvm() {
+    let version = version_meta_for(
+        "rustc 1.41.1
+binary: rustc
+commit-hash: unknown
+commit-date: unknown
+host: powerpc64le-unknown-linux-gnu
+release: 1.41.1
+LLVM version: 7.0",
+    )
+    .unwrap();
+    let other_llvm = version_meta_for(
+        "rustc 1.41.1
+binary: rustc
+commit-hash: unknown
+commit-date: unknown
+host: powerpc64le-unknown-linux-gnu
+release: 1.41.1
+LLVM version: 9.0.1",
+    )
+    .unwrap();
+
+    assert_ne!(version, other_llvm);
+    assert!(version.eq_ignoring_llvm(&other_llvm));
+    assert!(version.eq_ignoring_llvm(&version.clone().with_llvm_version(None)));
+
+    let other_host = other_llvm.with_host("x86_64-unknown-linux-gnu");
+    assert!(!version.eq_ignoring_llvm(&other_host));
+}
+
+#[test]
+fn parse_rustdoc() {
+    let version = rustc_version::rustdoc_version_meta_for(
+        "rustdoc 1.70.0 (90c541806 2023-05-31)
+binary: rustdoc
+commit-hash: 90c541806f23a127002de5b4038be731ba1458ca
+commit-date: 2023-05-31
+host: x86_64-unknown-linux-gnu
+release: 1.70.0
+LLVM version: 16.0.2",
+    )
+    .unwrap();
+
+    assert_eq!(version.semver, Version::parse("1.70.0").unwrap());
+    assert_eq!(
+        version.commit_hash,
+        Some("90c541806f23a127002de5b4038be731ba1458ca".into())
+    );
+    assert_eq!(version.channel, Channel::Stable);
+    assert_eq!(version.host, "x86_64-unknown-linux-gnu");
+    assert_eq!(
+        version.short_version_string,
+        "rustdoc 1.70.0 (90c541806 2023-05-31)"
+    );
+}
+
+#[test]
+fn rustdoc_smoketest() {
+    let version = rustc_version::rustdoc_version_meta().unwrap();
+    assert!(version.semver.major >= 1);
+}
+
+#[test]
+fn compiler_kind() {
+    let kind = |output| version_meta_for(output).unwrap().kind();
+
+    assert_eq!(
+        kind(
+            "rustc 1.70.0 (90c541806 2023-05-31)
+binary: rustc
+commit-hash: 90c541806f23a127002de5b4038be731ba1458ca
+commit-date: 2023-05-31
+host: x86_64-unknown-linux-gnu
+release: 1.70.0
+LLVM version: 16.0.2"
+        ),
+        CompilerKind::Rustc
+    );
+    assert_eq!(
+        kind(
+            "cargo 1.70.0 (ec8a8a0ca 2023-04-25)
+release: 1.70.0
+commit-hash: ec8a8a0cabb0e0cadef58902470f6c7ee7868bdc
+commit-date: 2023-04-25
+host: x86_64-unknown-linux-gnu
+libgit2: 1.6.4 (sys:0.17.1 vendored)
+libcurl: 8.0.1-DEV (sys:0.4.61+curl-8.0.1 vendored ssl:OpenSSL/1.1.1t)
+os: Arch Linux Rolling Release [64-bit]"
+        ),
+        CompilerKind::Cargo
+    );
+    assert_eq!(
+        kind(
+            "rustdoc 1.70.0 (90c541806 2023-05-31)
+binary: rustdoc
+commit-hash: 90c541806f23a127002de5b4038be731ba1458ca
+commit-date: 2023-05-31
+host: x86_64-unknown-linux-gnu
+release: 1.70.0
+LLVM version: 16.0.2"
+        ),
+        CompilerKind::Rustdoc
+    );
+    assert_eq!(
+        kind(
+            "miri 0.1.0 (c4c4b8c 2024-04-08)
+rustc 1.79.0-nightly (c4c4b8c0a 2024-04-08)
+binary: rustc
+commit-hash: c4c4b8c0ab3ac42ed67e2d64c4ccd67b21d1c2b1
+commit-date: 2024-04-08
+host: x86_64-unknown-linux-gnu
+release: 1.79.0-nightly
+LLVM version: 18.1.3"
+        ),
+        CompilerKind::Miri
+    );
+}
+
+#[test]
+fn semver_at_least() {
+    let version = version_meta_for(
+        "rustc 1.56.1 (59eed8a2a 2021-11-01)
+binary: rustc
+commit-hash: 59eed8a2aac0230a8b53e89d4e99d55912ba6b35
+commit-date: 2021-11-01
+host: x86_64-unknown-linux-gnu
+release: 1.56.1
+LLVM version: 13.0.0",
+    )
+    .unwrap();
+
+    assert!(version.semver_at_least("1.56").unwrap());
+    assert!(version.semver_at_least("1.56.1").unwrap());
+    assert!(version.semver_at_least("1.31").unwrap());
+    assert!(!version.semver_at_least("1.57").unwrap());
+    assert!(!version.semver_at_least("1.56.2").unwrap());
+    assert!(version.semver_at_least("1.56.1-beta.1").unwrap());
+
+    match version.semver_at_least("1") {
+        Err(Error::SemVerError(_)) => {}
+        _ => panic!("semver error expected"),
+    }
+}
+
+#[test]
+fn parse_not_a_compiler() {
+    let res = version_meta_for(
+        "hello world
+binary: rustc
+commit-hash: unknown
+commit-date: unknown
+host: x86_64-unknown-linux-gnu
+release: 1.3.0",
+    );
+    match res {
+        Err(Error::NotACompilerVersion(ref line)) => assert_eq!(line, "hello world"),
+        _ => panic!("not a compiler version error expected"),
+    }
+}
+
+#[test]
+fn parse_with_options() {
+    use rustc_version::{version_meta_for_with_options, ParseOptions};
+
+    let output = "warning: ignoring unknown config
+rustc 1.80.0-experimental
+binary: rustc
+commit-hash: unknown
+commit-date: unknown
+host: x86_64-unknown-linux-gnu
+release: 1.80.0-experimental";
+
+    let strict = ParseOptions::new();
+    assert!(version_meta_for_with_options(output, &strict).is_err());
+
+    let extra_lines = ParseOptions::new().allow_extra_lines(true);
+    match version_meta_for_with_options(output, &extra_lines) {
+        Err(Error::UnknownPreReleaseTag(ref tag)) => assert_eq!(tag, "experimental"),
+        _ => panic!("unknown pre-release tag error expected"),
+    }
+
+    let relaxed = extra_lines.allow_unknown_channel(true);
+    let version = version_meta_for_with_options(output, &relaxed).unwrap();
+    assert_eq!(version.channel, Channel::Dev);
+    assert_eq!(version.commit_hash, None);
+
+    let hash_required = relaxed.require_commit_hash(true);
+    match version_meta_for_with_options(output, &hash_required) {
+        Err(Error::UnexpectedVersionFormat) => {}
+        _ => panic!("unexpected version format error expected"),
+    }
+}
+
+#[test]
+fn parse_stray_carriage_returns() {
+    let output = concat!(
+        "rustc 1.3.0 (9a92aaf19 2015-09-15)\r\r\n",
+        "binary: rustc\r\r\n",
+        "commit-hash: 9a92aaf19a64603b02b4130fe52958cc12488900\r\r\n",
+        "commit-date: 2015-09-15\r\r\n",
+        "host: x86_64-unknown-linux-gnu\r\r\n",
+        "release: 1.3.0\r\r\n",
+    );
+
+    // field values are trimmed, the short version string is kept as-is
+    let version = version_meta_for(output).unwrap();
+    assert_eq!(version.host, "x86_64-unknown-linux-gnu");
+    assert_eq!(version.semver, Version::new(1, 3, 0));
+    assert_eq!(
+        version.short_version_string,
+        "rustc 1.3.0 (9a92aaf19 2015-09-15)\r"
+    );
+}
+
+#[test]
+fn has_stable_feature() {
+    let meta = version_meta_for(
+        "rustc 1.64.0 (a55dd71d5 2022-09-19)
+binary: rustc
+commit-hash: a55dd71d5fb0ec5a6a3a9e8c27b2127ba491ce52
+commit-date: 2022-09-19
+host: x86_64-unknown-linux-gnu
+release: 1.64.0",
+    )
+    .unwrap();
+    let min = Version::new(1, 65, 0);
+
+    assert!(!meta.has_stable_feature(min.clone()));
+    assert!(meta
+        .clone()
+        .with_semver(Version::new(1, 65, 0))
+        .has_stable_feature(min.clone()));
+    assert!(meta
+        .clone()
+        .with_semver(Version::parse("1.65.0-beta.2").unwrap())
+        .with_channel(Channel::Beta)
+        .has_stable_feature(min.clone()));
+    assert!(meta.with_channel(Channel::Nightly).has_stable_feature(min));
+}
+
+#[test]
+fn llvm_version_patch_ordering() {
+    let v17_0_5: LlvmVersion = "17.0.5".parse().unwrap();
+    let v17_0_6: LlvmVersion = "17.0.6".parse().unwrap();
+    let v18_0_0: LlvmVersion = "18.0.0".parse().unwrap();
+
+    assert_eq!(v17_0_6.major_minor_patch(), (17, 0, Some(6)));
+    assert_eq!(
+        "17.0".parse::<LlvmVersion>().unwrap().major_minor_patch(),
+        (17, 0, None)
+    );
+    assert_eq!(v17_0_6.to_string(), "17.0.6");
+    assert!(v17_0_5 < v17_0_6);
+    assert!(v17_0_6 < v18_0_0);
+}
+
+#[test]
+fn verbose_string_round_trip() {
+    let fixtures = [
+        "rustc 1.0.0 (a59de37e9 2015-05-13) (built 2015-05-14)
+binary: rustc
+commit-hash: a59de37e99060162a2674e3ff45409ac73595c0e
+commit-date: 2015-05-13
+build-date: 2015-05-14
+host: x86_64-unknown-linux-gnu
+release: 1.0.0",
+        "rustc 1.50.0-nightly (2020-12-31)
+binary: rustc
+commit-hash: unknown
+commit-date: unknown
+host: x86_64-unknown-linux-gnu
+release: 1.50.0-nightly
+LLVM version: 11",
+        "rustc 1.80.0-dev
+binary: rustc
+commit-hash: unknown
+commit-date: unknown
+host: x86_64-unknown-linux-gnu
+release: 1.80.0-dev
+LLVM version: 18.1.7
+LLVM version: 17.0",
+        "miri 0.1.0 (c4c4b8c 2024-04-08)
+rustc 1.79.0-nightly (c4c4b8c0a 2024-04-08)
+binary: rustc
+commit-hash: c4c4b8c0ab3ac42ed67e2d64c4ccd67b21d1c2b1
+commit-date: 2024-04-08
+host: x86_64-unknown-linux-gnu
+release: 1.79.0-nightly
+LLVM version: 18.1.3",
+    ];
+
+    for fixture in fixtures.iter() {
+        let meta = version_meta_for(fixture).unwrap();
+        let verbose = meta.to_verbose_string();
+        let parsed = version_meta_for(&verbose).unwrap();
+        assert_eq!(parsed.raw(), Some(&*verbose));
+        assert_eq!(parsed, meta);
+        assert_eq!(VersionMeta::from_json(&meta.to_json()).unwrap(), meta);
+    }
+}
+
+#[test]
+fn channel_hint_from_program() {
+    use rustc_version::channel_hint_from_program;
+    use std::ffi::OsStr;
+
+    assert_eq!(
+        channel_hint_from_program(OsStr::new("rustc-nightly")),
+        Some(Channel::Nightly)
+    );
+    assert_eq!(channel_hint_from_program(OsStr::new("rustc")), None);
+    assert_eq!(
+        channel_hint_from_program(OsStr::new("/opt/rust/bin/rustc-beta")),
+        Some(Channel::Beta)
+    );
+    assert_eq!(
+        channel_hint_from_program(OsStr::new("rustc-1.70.0.exe")),
+        Some(Channel::Stable)
+    );
+    assert_eq!(
+        channel_hint_from_program(OsStr::new("/usr/bin/rustc")),
+        None
+    );
+    assert_eq!(channel_hint_from_program(OsStr::new("rustc-custom")), None);
+}
+
+#[test]
+fn commit_url() {
+    let version = stable_1_47();
+    assert_eq!(
+        version.commit_url().unwrap(),
+        "https://github.com/rust-lang/rust/commit/18bf6b4f01a6feaf7259ba7cdae58031af1b7b39"
+    );
+    assert_eq!(version.with_commit_hash(None).commit_url(), None);
+}
+
+#[test]
+fn parse_partial_release() {
+    use rustc_version::{version_meta_for_with_options, ParseOptions};
+
+    let output = "rustc 1.70-custom
+binary: rustc
+commit-hash: unknown
+commit-date: unknown
+host: x86_64-unknown-linux-gnu
+release: 1.70";
+
+    match version_meta_for(output) {
+        Err(Error::SemVerError(_)) => {}
+        _ => panic!("semver error expected"),
+    }
+
+    let options = ParseOptions::new().allow_partial_release(true);
+    let version = version_meta_for_with_options(output, &options).unwrap();
+    assert_eq!(version.semver, Version::new(1, 70, 0));
+    assert_eq!(version.channel, Channel::Stable);
+}
+
+#[test]
+fn channel_variants() {
+    let variants = Channel::variants();
+    assert_eq!(variants.len(), 4);
+    assert_eq!(
+        variants,
+        [
+            Channel::Dev,
+            Channel::Nightly,
+            Channel::Beta,
+            Channel::Stable
+        ]
+    );
+    assert!(variants.windows(2).all(|w| w[0] < w[1]));
+}
+
+#[test]
+fn version_meta_diff() {
+    let old = version_meta_for(
+        "rustc 1.69.0 (84c898d65 2023-04-16)
+binary: rustc
+commit-hash: 84c898d65adf2f39a5a98507f1fe0ce10a2b8dbc
+commit-date: 2023-04-16
+host: x86_64-unknown-linux-gnu
+release: 1.69.0
+LLVM version: 15.0.7",
+    )
+    .unwrap();
+    assert!(old.diff(&old).is_empty());
+
+    let new = old
+        .clone()
+        .with_semver(Version::new(1, 70, 0))
+        .with_commit_hash(None);
+    assert_eq!(
+        old.diff(&new),
+        vec![
+            "semver: 1.69.0 -> 1.70.0".to_owned(),
+            "commit-hash: 84c898d65adf2f39a5a98507f1fe0ce10a2b8dbc -> unknown".to_owned(),
+        ]
+    );
+}
+
+#[test]
+fn for_command_long_flags() {
+    let long = VersionMeta::for_command_long_flags(Command::new("rustc")).unwrap();
+    let short = VersionMeta::for_command(Command::new("rustc")).unwrap();
+    assert_eq!(long, short);
+}
+
+#[test]
+fn llvm_version_parse_error_includes_input() {
+    let message = |s: &str| s.parse::<LlvmVersion>().unwrap_err().to_string();
+
+    assert!(message("17.01").contains("`01`"));
+    assert!(message("17.+1").contains("`+1`"));
+    assert!(message("3").contains("`3`"));
+    assert!(message("17.0.6.1").contains("`17.0.6.1`"));
+}
+
+#[test]
+fn error_includes_command() {
+    use rustc_version::{version_meta_for_path_with_args, version_meta_with_env};
+
+    let no_args: [&str; 0] = [];
+    let err =
+        version_meta_for_path_with_args("rustc-version-does-not-exist", &no_args).unwrap_err();
+    match err {
+        Error::CouldNotExecuteCommand { ref command, .. } => {
+            assert_eq!(command, "\"rustc-version-does-not-exist\" \"-vV\"")
+        }
+        _ => panic!("could not execute command error expected"),
+    }
+
+    let err = version_meta_for_path_with_args("rustc", &["--FOO"]).unwrap_err();
+    assert!(err.to_string().contains("\"rustc\" \"--FOO\" \"-vV\""));
+
+    // the program of a command passed in is unknown
+    let mut cmd = Command::new("rustc");
+    cmd.arg("--FOO").env("RUSTC_VERSION_SECRET", "hunter2");
+    match VersionMeta::for_command(cmd).unwrap_err() {
+        Error::CommandError { ref command, .. } => assert_eq!(command, "<command> \"-vV\""),
+        _ => panic!("command error expected"),
+    }
+
+    // environment variables may hold secrets, so they are left out
+    let vars = vec![
+        ("RUSTC".into(), "rustc-version-does-not-exist".into()),
+        ("RUSTC_VERSION_SECRET".into(), "hunter2".into()),
+    ];
+    let err = version_meta_with_env(vars).unwrap_err();
+    assert!(!err.to_string().contains("hunter2"));
+    assert!(err.to_string().contains("rustc-version-does-not-exist"));
+}
+
+#[test]
+fn meets_package_msrv() {
+    use rustc_version::meets_package_msrv;
+
+    assert!(meets_package_msrv("1.31").unwrap());
+    assert!(meets_package_msrv("1.31.1").unwrap());
+    assert!(!meets_package_msrv("999.0").unwrap());
+    assert!(!meets_package_msrv("999.0.0").unwrap());
+    assert!(meets_package_msrv("1.x").is_err());
+}
+
+#[test]
+fn host_pointer_width() {
+    let meta = stable_1_47();
+    let width = |host: &str| meta.clone().with_host(host).host_pointer_width();
+
+    assert_eq!(width("x86_64-unknown-linux-gnu"), Some(64));
+    assert_eq!(width("x86_64-unknown-linux-gnux32"), Some(32));
+    assert_eq!(width("i686-pc-windows-msvc"), Some(32));
+    assert_eq!(width("aarch64-apple-darwin"), Some(64));
+    assert_eq!(width("arm64_32-apple-watchos"), Some(32));
+    assert_eq!(width("thumbv7em-none-eabihf"), Some(32));
+    assert_eq!(width("avr-unknown-gnu-atmega328"), Some(16));
+    assert_eq!(width("mips64-unknown-linux-gnuabi64"), Some(64));
+    assert_eq!(width("mipsisa64r6-unknown-linux-gnuabi64"), Some(64));
+    assert_eq!(width("mipsisa64r6el-unknown-linux-gnuabi64"), Some(64));
+    assert_eq!(width("mipsisa32r6-unknown-linux-gnu"), Some(32));
+    assert_eq!(width("unknown-unknown-none"), None);
+}
+
+#[test]
+fn version_meta_to_json() {
+    let version = stable_1_47();
+    assert_eq!(
+        version.to_json(),
+        concat!(
+            "{\"semver\":\"1.47.0\",",
+            "\"commit_hash\":\"18bf6b4f01a6feaf7259ba7cdae58031af1b7b39\",",
+            "\"commit_date\":\"2020-10-07\",\"build_date\":null,",
+            "\"channel\":\"stable\",\"host\":\"powerpc64le-unknown-linux-gnu\",",
+            "\"short_version_string\":\"rustc 1.47.0 (18bf6b4f0 2020-10-07)\",",
+            "\"llvm_version\":\"11.0\"}"
+        )
+    );
+
+    let json = version
+        .with_short_version_string("rustc \"custom\"\\\n\u{1}")
+        .to_json();
+    assert!(json.contains("\"short_version_string\":\"rustc \\\"custom\\\"\\\\\\n\\u0001\""));
+}
+
+#[test]
+fn parse_swapped_commit_lines() {
+    let version = version_meta_for(
+        "rustc 1.3.0 (9a92aaf19 2015-09-15)
+binary: rustc
+commit-date: 2015-09-15
+commit-hash: 9a92aaf19a64603b02b4130fe52958cc12488900
+host: x86_64-unknown-linux-gnu
+release: 1.3.0",
+    )
+    .unwrap();
+
+    assert_eq!(
+        version.commit_hash,
+        Some("9a92aaf19a64603b02b4130fe52958cc12488900".into())
+    );
+    assert_eq!(version.commit_date, Some("2015-09-15".into()));
+}
+
+#[test]
+fn is_prerelease() {
+    let version = stable_1_47();
+
+    assert!(!version.is_prerelease());
+    for &channel in &[Channel::Dev, Channel::Nightly, Channel::Beta] {
+        assert!(version.clone().with_channel(channel).is_prerelease());
+    }
+}
+
+#[test]
+fn print_values_for() {
+    use rustc_version::print_values_for;
+
+    let sysroot = print_values_for(Command::new("rustc"), "sysroot").unwrap();
+    assert_eq!(sysroot.len(), 1);
+    assert!(!sysroot[0].is_empty());
+
+    let targets = print_values_for(Command::new("rustc"), "target-list").unwrap();
+    assert!(targets.len() > 1);
+    assert_eq!(targets, target_list_for(Command::new("rustc")).unwrap());
+}
+
+#[test]
+fn unexpected_output() {
+    let mut cmd = Command::new("rustc");
+    cmd.arg("--version");
+    let version = str::from_utf8(&cmd.output().unwrap().stdout)
+        .unwrap()
+        .trim()
+        .to_owned();
+
+    let mut cmd = Command::new("rustc");
+    cmd.arg("--version");
+    let err = VersionMeta::for_command_raw(cmd).unwrap_err();
+    match err {
+        Error::UnexpectedOutput(ref snippet) => assert_eq!(*snippet, version),
+        _ => panic!("unexpected output error expected"),
+    }
+    assert!(err.to_string().contains(&version));
+}
+
+#[test]
+#[cfg(unix)]
+fn unexpected_output_is_truncated() {
+    let mut cmd = Command::new("sh");
+    cmd.arg("-c")
+        .arg("echo rustc 1.3.0; for i in 1 2 3 4 5 6 7 8; do echo line $i; done");
+    match VersionMeta::for_command_raw(cmd) {
+        Err(Error::UnexpectedOutput(ref snippet)) => {
+            assert_eq!(snippet, "rustc 1.3.0\nline 1\nline 2\nline 3\nline 4\n...")
+        }
+        _ => panic!("unexpected output error expected"),
+    }
+}
+
+#[test]
+fn stable_equivalent() {
+    let version = version_meta_for(
+        "rustc 1.80.0-nightly (72fdf913c 2024-06-05)
+binary: rustc
+commit-hash: 72fdf913c53dd0e75313ba83e4aa80df3f6e2871
+commit-date: 2024-06-05
+host: x86_64-unknown-linux-gnu
+release: 1.80.0-nightly
+LLVM version: 18.1.6",
+    )
+    .unwrap();
+    assert_eq!(version.stable_equivalent(), Version::new(1, 80, 0));
+
+    let beta = version
+        .clone()
+        .with_semver(Version::parse("1.79.0-beta.6").unwrap())
+        .with_channel(Channel::Beta);
+    assert_eq!(beta.stable_equivalent(), Version::new(1, 79, 0));
+
+    let stable = version
+        .with_semver(Version::new(1, 78, 0))
+        .with_channel(Channel::Stable);
+    assert_eq!(stable.stable_equivalent(), Version::new(1, 78, 0));
+}
+
+#[test]
+fn evaluate_reqs() {
+    let version = version_meta_for(
+        "rustc 1.70.0 (90c541806 2023-05-31)
+binary: rustc
+commit-hash: 90c541806f23a127002de5b4038be731ba1458ca
+commit-date: 2023-05-31
+host: x86_64-unknown-linux-gnu
+release: 1.70.0",
+    )
+    .unwrap();
+
+    assert_eq!(
+        version
+            .evaluate_reqs(vec![">=1.65", "<1.70", "^1.70.0", ">=1.71"])
+            .unwrap(),
+        vec![
+            (">=1.65", true),
+            ("<1.70", false),
+            ("^1.70.0", true),
+            (">=1.71", false)
+        ]
+    );
+
+    match version.evaluate_reqs(vec![">=1.65", "not a req"]) {
+        Err(Error::ReqParseError { ref req, .. }) => assert_eq!(req, "not a req"),
+        _ => panic!("req parse error expected"),
+    }
+}
+
+#[test]
+fn compiler_debug_assertions() {
+    let output = "rustc 1.80.0-dev
+binary: rustc
+commit-hash: unknown
+commit-date: unknown
+host: x86_64-unknown-linux-gnu
+release: 1.80.0-dev
+debug-assertions: yes
+LLVM version: 18.1.7";
+
+    let version = version_meta_for(output).unwrap();
+    assert_eq!(version.compiler_debug_assertions(), Some(true));
+    assert_eq!(
+        version_meta_for(&version.to_verbose_string())
+            .unwrap()
+            .compiler_debug_assertions(),
+        Some(true)
+    );
+
+    let version =
+        version_meta_for(&output.replace("debug-assertions: yes", "debug-assertions: no")).unwrap();
+    assert_eq!(version.compiler_debug_assertions(), Some(false));
+
+    let version = version_meta_for(&output.replace("debug-assertions: yes\n", "")).unwrap();
+    assert_eq!(version.compiler_debug_assertions(), None);
+}
+
+#[test]
+fn version_meta_from_json() {
+    let version = stable_1_47();
+    assert_eq!(VersionMeta::from_json(&version.to_json()).unwrap(), version);
+
+    let nightly = version
+        .with_channel(Channel::Nightly)
+        .with_commit_hash(None)
+        .with_short_version_string("rustc \"custom\"\\\n\u{1}\u{1F980}");
+    assert_eq!(VersionMeta::from_json(&nightly.to_json()).unwrap(), nightly);
+
+    let json = r#" { "semver": "1.3.0", "channel": "stable", "host": "x86_64-unknown-linux-gnu",
+        "short_version_string": "rustc 1.3.0 \ud83e\udd80", "commit_hash": null } "#;
+    let parsed = VersionMeta::from_json(json).unwrap();
+    assert_eq!(parsed.semver, Version::new(1, 3, 0));
+    assert_eq!(parsed.short_version_string, "rustc 1.3.0 \u{1F980}");
+    assert_eq!(parsed.commit_hash, None);
+    assert_eq!(parsed.llvm_version, None);
+    assert_eq!(parsed.raw(), None);
+
+    for invalid in &[
+        "",
+        "{",
+        "{\"semver\": \"1.3.0\"}",
+        "{\"semver\": 1}",
+        "{\"semver\": \"1.3.0\", \"channel\": \"stable\", \"host\": \"x\"} x",
+    ] {
+        match VersionMeta::from_json(invalid) {
+            Err(Error::InvalidJson(_)) => {}
+            _ => panic!("invalid JSON error expected for {:?}", invalid),
+        }
+    }
+}
+
+#[test]
+fn satisfies_msrv() {
+    let version = version_meta_for(
+        "rustc 1.70.0 (90c541806 2023-05-31)
+binary: rustc
+commit-hash: 90c541806f23a127002de5b4038be731ba1458ca
+commit-date: 2023-05-31
+host: x86_64-unknown-linux-gnu
+release: 1.70.0",
+    )
+    .unwrap();
+
+    assert!(version.satisfies_msrv(1, 70));
+    assert!(version.satisfies_msrv(1, 69));
+    assert!(!version.satisfies_msrv(1, 71));
+
+    let patched = version.clone().with_semver(Version::new(1, 70, 3));
+    assert!(patched.satisfies_msrv(1, 70));
+    assert!(!patched.satisfies_msrv(1, 71));
+
+    let older = version.clone().with_semver(Version::new(1, 69, 9));
+    assert!(!older.satisfies_msrv(1, 70));
+
+    let nightly = version
+        .with_semver(Version::parse("1.70.0-nightly").unwrap())
+        .with_channel(Channel::Nightly);
+    assert!(nightly.satisfies_msrv(1, 70));
+}
+
+#[test]
+fn parse_release_before_host() {
+    let version = version_meta_for(
+        "rustc 1.3.0 (9a92aaf19 2015-09-15)
+binary: rustc
+commit-hash: 9a92aaf19a64603b02b4130fe52958cc12488900
+commit-date: 2015-09-15
+release: 1.3.0
+host: x86_64-unknown-linux-gnu",
+    )
+    .unwrap();
+
+    assert_eq!(version.semver, Version::new(1, 3, 0));
+    assert_eq!(version.host, "x86_64-unknown-linux-gnu");
+}
+
+#[test]
+fn channel_default() {
+    assert_eq!(Channel::default(), Channel::Stable);
+}
+
+#[test]
+fn same_release_different_channel() {
+    let stable = version_meta_for(
+        "rustc 1.80.0 (051478957 2024-07-21)
+binary: rustc
+commit-hash: 051478957371ee0084a7c0913941d2a8c4757bb9
+commit-date: 2024-07-21
+host: x86_64-unknown-linux-gnu
+release: 1.80.0
+LLVM version: 18.1.7",
+    )
+    .unwrap();
+    let nightly = stable
+        .clone()
+        .with_semver(Version::parse("1.80.0-nightly").unwrap())
+        .with_channel(Channel::Nightly);
+
+    assert!(stable.same_release_different_channel(&nightly));
+    assert!(nightly.same_release_different_channel(&stable));
+    assert!(!stable.same_release_different_channel(&stable));
+    assert!(!stable.same_release_different_channel(
+        &nightly.with_semver(Version::parse("1.81.0-nightly").unwrap())
+    ));
+}
+
+#[test]
+fn probe_for() {
+    use rustc_version::{probe_for, Cfg};
+    use std::path::Path;
+
+    let probe = probe_for(Path::new("rustc")).unwrap();
+    assert_eq!(
+        probe.meta,
+        VersionMeta::for_command(Command::new("rustc")).unwrap()
+    );
+    assert!(probe.cfgs.contains(&Cfg {
+        name: "debug_assertions".into(),
+        value: None,
+    }));
+    let target_os = probe
+        .cfgs
+        .iter()
+        .find(|cfg| cfg.name == "target_os")
+        .unwrap();
+    assert_eq!(
+        target_os.value.as_ref().map(|s| &**s),
+        Some(env::consts::OS)
+    );
+}
+
+#[test]
+fn parse_llvm_version_rust_suffix() {
+    use rustc_version::{version_meta_for_with_options, ParseOptions};
+
+    let suffixed = "17.0.6-rust-1.80.0-stable";
+    assert!(suffixed.parse::<LlvmVersion>().is_err());
+    let v = LlvmVersion::parse_lenient(suffixed).unwrap();
+    assert_eq!(v.major_minor_patch(), (17, 0, Some(6)));
+    assert_eq!(
+        LlvmVersion::parse_lenient("17.0.6")
+            .unwrap()
+            .major_minor_patch(),
+        (17, 0, Some(6))
+    );
+
+    let output = "rustc 1.80.0 (051478957 2024-07-21)
+binary: rustc
+commit-hash: 051478957371ee0084a7c0913941d2a8c4757bb9
+commit-date: 2024-07-21
+host: x86_64-unknown-linux-gnu
+release: 1.80.0
+LLVM version: 17.0.6-rust-1.80.0-stable";
+
+    match version_meta_for(output) {
+        Err(Error::LlvmVersionError(_)) => {}
+        _ => panic!("LLVM version error expected"),
+    }
+
+    let options = ParseOptions::new().allow_llvm_suffix(true);
+    let version = version_meta_for_with_options(output, &options).unwrap();
+    assert_eq!(version.llvm_version, Some(v));
+    assert_eq!(version.llvm_version_precision(), Some(3));
+}
+
+#[test]
+fn default_edition() {
+    let version = stable_1_47();
+    let edition = |v: &str| {
+        version
+            .clone()
+            .with_semver(Version::parse(v).unwrap())
+            .default_edition()
+    };
+
+    assert_eq!(edition("1.3.0"), "2015");
+    assert_eq!(edition("1.30.1"), "2015");
+    assert_eq!(edition("1.31.0"), "2018");
+    assert_eq!(edition("1.55.0"), "2018");
+    assert_eq!(edition("1.56.0"), "2021");
+    assert_eq!(edition("1.84.1"), "2021");
+    assert_eq!(edition("1.85.0"), "2024");
+    assert_eq!(edition("1.85.0-nightly"), "2024");
+}
+
+#[test]
+fn without_metadata() {
+    let build = |hash: &str, date: &str| {
+        version_meta_for(&format!(
+            "rustc 1.80.0-dev ({} {})
+binary: rustc
+commit-hash: {}
+commit-date: {}
+host: x86_64-unknown-linux-gnu
+release: 1.80.0-dev
+LLVM version: 18.1.7",
+            &hash[..9],
+            date,
+            hash,
+            date
+        ))
+        .unwrap()
+    };
+    let first = build("051478957371ee0084a7c0913941d2a8c4757bb9", "2024-07-21");
+    let second = build("72fdf913c53dd0e75313ba83e4aa80df3f6e2871", "2024-06-05");
+
+    assert_ne!(first, second);
+    assert_eq!(first.without_metadata(), second.without_metadata());
+
+    let stripped = first.without_metadata();
+    assert_eq!(stripped.commit_hash, None);
+    assert_eq!(stripped.commit_date, None);
+    assert_eq!(stripped.short_version_string, "rustc 1.80.0-dev");
+    assert_eq!(stripped.semver, first.semver);
+    assert_eq!(stripped.llvm_version, first.llvm_version);
+}
+
+#[test]
+fn feature_accepted_for() {
+    use rustc_version::feature_accepted_for;
+    use std::path::Path;
+
+    let rustc = Path::new("rustc");
+    let meta = VersionMeta::for_command(Command::new(rustc)).unwrap();
+    // stable since Rust 1.26, so nightly compilers accept it with a warning
+    let accepted = feature_accepted_for(rustc, "i128_type").unwrap();
+    match meta.channel {
+        Channel::Nightly | Channel::Dev => assert!(accepted),
+        Channel::Beta | Channel::Stable => {
+            assert_eq!(accepted, env::var_os("RUSTC_BOOTSTRAP").is_some())
+        }
+    }
+    assert!(!feature_accepted_for(rustc, "rustc_version_no_such_feature").unwrap());
+
+    match feature_accepted_for(Path::new("rustc-version-does-not-exist"), "i128_type") {
+        Err(Error::CouldNotExecuteCommand { .. }) => {}
+        _ => panic!("could not execute command error expected"),
+    }
+
+    for &invalid in &["../../home/user/project", "foo)] fn x() {}", ""] {
+        match feature_accepted_for(rustc, invalid) {
+            Err(Error::InvalidFeatureName(ref feature)) => assert_eq!(feature, invalid),
+            _ => panic!("invalid feature name error expected"),
+        }
+    }
+}
+
+#[test]
+fn channel_consistent_with_semver() {
+    let stable = version_meta_for(
+        "rustc 1.70.0 (90c541806 2023-05-31)
+binary: rustc
+commit-hash: 90c541806f23a127002de5b4038be731ba1458ca
+commit-date: 2023-05-31
+host: x86_64-unknown-linux-gnu
+release: 1.70.0",
+    )
+    .unwrap();
+    let with = |pre: &str, channel: Channel| {
+        stable
+            .clone()
+            .with_semver(Version::parse(pre).unwrap())
+            .with_channel(channel)
+    };
+
+    assert!(stable.channel_consistent_with_semver());
+    assert!(with("1.70.0-beta.3", Channel::Beta).channel_consistent_with_semver());
+    assert!(with("1.70.0-rc.1", Channel::Beta).channel_consistent_with_semver());
+    assert!(with("1.71.0-nightly", Channel::Nightly).channel_consistent_with_semver());
+    assert!(with("1.71.0-dev", Channel::Dev).channel_consistent_with_semver());
+
+    assert!(!with("1.70.0", Channel::Nightly).channel_consistent_with_semver());
+    assert!(!with("1.71.0-nightly", Channel::Stable).channel_consistent_with_semver());
+    assert!(!with("1.70.0-beta.3", Channel::Nightly).channel_consistent_with_semver());
+    assert!(!with("1.70.0", Channel::Dev).channel_consistent_with_semver());
+}
+
+#[test]
+fn expected_cargo_version() {
+    let version = version_meta_for(
+        "rustc 1.80.0-nightly (72fdf913c 2024-06-05)
+binary: rustc
+commit-hash: 72fdf913c53dd0e75313ba83e4aa80df3f6e2871
+commit-date: 2024-06-05
+host: x86_64-unknown-linux-gnu
+release: 1.80.0-nightly
+LLVM version: 18.1.6",
+    )
+    .unwrap();
+    assert_eq!(version.expected_cargo_version(), version.semver);
+}
+
+#[test]
+fn version_meta_from_file() {
+    use rustc_version::version_meta_from_file;
+
+    let output = "rustc 1.3.0 (9a92aaf19 2015-09-15)
+binary: rustc
+commit-hash: 9a92aaf19a64603b02b4130fe52958cc12488900
+commit-date: 2015-09-15
+host: x86_64-unknown-linux-gnu
+release: 1.3.0
+";
+    let dir = env::temp_dir().join("rustc_version_from_file_test");
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(".rustc-version");
+    fs::write(&path, output).unwrap();
+
+    assert_eq!(
+        version_meta_from_file(&path).unwrap(),
+        version_meta_for(output).unwrap()
+    );
+
+    match version_meta_from_file(dir.join("does-not-exist")) {
+        Err(Error::CouldNotReadFile(ref e)) => assert_eq!(e.kind(), io::ErrorKind::NotFound),
+        _ => panic!("could not read file error expected"),
+    }
+}
+
+#[test]
+fn recommend_upgrade() {
+    let stable = version_meta_for(
+        "rustc 1.70.0 (90c541806 2023-05-31)
+binary: rustc
+commit-hash: 90c541806f23a127002de5b4038be731ba1458ca
+commit-date: 2023-05-31
+host: x86_64-unknown-linux-gnu
+release: 1.70.0",
+    )
+    .unwrap();
+
+    assert_eq!(stable.recommend_upgrade(&Version::new(1, 70, 0)), None);
+    assert_eq!(stable.recommend_upgrade(&Version::new(1, 69, 0)), None);
+    assert_eq!(
+        stable.recommend_upgrade(&Version::new(1, 72, 1)).unwrap(),
+        "rustc 1.70.0 is older than the latest stable release 1.72.1; \
+         consider running `rustup update stable`"
+    );
+
+    let nightly = stable
+        .with_semver(Version::parse("1.72.0-nightly").unwrap())
+        .with_channel(Channel::Nightly);
+    assert_eq!(nightly.recommend_upgrade(&Version::new(1, 71, 0)), None);
+    assert!(nightly
+        .recommend_upgrade(&Version::new(1, 72, 0))
+        .unwrap()
+        .ends_with("`rustup update nightly`"));
+}
+
+#[test]
+fn rustc_range_for_llvm() {
+    use rustc_version::rustc_range_for_llvm;
+
+    assert_eq!(
+        rustc_range_for_llvm(11),
+        Some((Version::new(1, 47, 0), Version::new(1, 51, 0)))
+    );
+    assert_eq!(
+        rustc_range_for_llvm(15),
+        Some((Version::new(1, 65, 0), Version::new(1, 69, 0)))
+    );
+    assert_eq!(
+        rustc_range_for_llvm(20),
+        Some((Version::new(1, 87, 0), Version::new(1, 90, 0)))
+    );
+    assert_eq!(rustc_range_for_llvm(3), None);
+}
+
+#[test]
+fn parse_ignoring_key_case() {
+    use rustc_version::{version_meta_for_with_options, ParseOptions};
+
+    let output = "rustc 1.47.0 (18bf6b4f0 2020-10-07)
+Binary: rustc
+Commit-Hash: 18bf6b4f01a6feaf7259ba7cdae58031af1b7b39
+Commit-Date: 2020-10-07
+Host: x86_64-unknown-linux-gnu
+Release: 1.47.0
+llvm version: 11.0";
+
+    match version_meta_for(output) {
+        Err(Error::UnexpectedVersionFormat) => {}
+        _ => panic!("unexpected version format error expected"),
+    }
+
+    let options = ParseOptions::new().ignore_key_case(true);
+    let version = version_meta_for_with_options(output, &options).unwrap();
+    assert_eq!(version.semver, Version::new(1, 47, 0));
+    assert_eq!(version.host, "x86_64-unknown-linux-gnu");
+    assert_eq!(version.commit_date, Some("2020-10-07".into()));
+    assert_eq!(version.llvm_version, Some("11.0".parse().unwrap()));
+}
+
+#[test]
+fn check_thresholds() {
+    let version = version_meta_for(
+        "rustc 1.70.0 (90c541806 2023-05-31)
+binary: rustc
+commit-hash: 90c541806f23a127002de5b4038be731ba1458ca
+commit-date: 2023-05-31
+host: x86_64-unknown-linux-gnu
+release: 1.70.0",
+    )
+    .unwrap();
+
+    let thresholds = [
+        ("has_const_generics", Version::new(1, 51, 0)),
+        ("has_once_cell", Version::new(1, 70, 0)),
+        ("has_let_else_fix", Version::new(1, 70, 1)),
+        ("has_async_fn_in_trait", Version::new(1, 75, 0)),
+    ];
+    assert_eq!(
+        version.check(&thresholds),
+        vec![
+            ("has_const_generics", true),
+            ("has_once_cell", true),
+            ("has_let_else_fix", false),
+            ("has_async_fn_in_trait", false),
+        ]
+    );
+}
+
+#[test]
+fn apple_arch() {
+    let version = stable_1_47().with_host("x86_64-apple-darwin");
+    assert_eq!(version.apple_arch(), Some("x86_64"));
+
+    let arm = version.clone().with_host("aarch64-apple-darwin");
+    assert_eq!(arm.apple_arch(), Some("aarch64"));
+
+    let linux = version.with_host("x86_64-unknown-linux-gnu");
+    assert_eq!(linux.apple_arch(), None);
+}
+
+#[test]
+fn error_context() {
+    use std::error::Error as StdError;
+
+    let err = Error::UnknownPreReleaseTag("alpha".into())
+        .context("while detecting the toolchain for wasm32-unknown-unknown");
+    assert_eq!(
+        err.to_string(),
+        format!(
+            "while detecting the toolchain for wasm32-unknown-unknown: {}",
+            Error::UnknownPreReleaseTag("alpha".into())
+        )
+    );
+    assert_eq!(err.code(), "pre_release_tag");
+    match err.source().and_then(|e| e.downcast_ref::<Error>()) {
+        Some(Error::UnknownPreReleaseTag(tag)) => assert_eq!(tag, "alpha"),
+        _ => panic!("unknown pre-release tag error expected as source"),
+    }
+}
+
+#[test]
+fn beta_commit_date() {
+    let beta = version_meta_for(
+        "rustc 1.79.0-beta.6 (2b5d8ba3c 2024-06-01)
+binary: rustc
+commit-hash: 2b5d8ba3c4e9a4a7ee8eb8e6fa2b4d4a1e2c2e5b
+commit-date: 2024-06-01
+host: x86_64-unknown-linux-gnu
+release: 1.79.0-beta.6
+LLVM version: 18.1.7",
+    )
+    .unwrap();
+    assert_eq!(beta.beta_commit_date(), Some("2024-06-01"));
+    assert_eq!(beta.nightly_date(), None);
+
+    let stable = beta
+        .with_semver(Version::new(1, 79, 0))
+        .with_channel(Channel::Stable);
+    assert_eq!(stable.beta_commit_date(), None);
+}
+
+#[test]
+fn summary_fields() {
+    let version = stable_1_47();
+    assert_eq!(
+        version.summary_fields(),
+        vec![
+            ("Version", "1.47.0".to_owned()),
+            ("Channel", "stable".to_owned()),
+            ("Host", "powerpc64le-unknown-linux-gnu".to_owned()),
+            (
+                "Commit",
+                "18bf6b4f01a6feaf7259ba7cdae58031af1b7b39".to_owned()
+            ),
+            ("Date", "2020-10-07".to_owned()),
+            ("LLVM", "11.0".to_owned()),
+        ]
+    );
+
+    let labels: Vec<_> = version
+        .with_commit_hash(None)
+        .with_llvm_version(None)
+        .summary_fields()
+        .into_iter()
+        .map(|(label, _)| label)
+        .collect();
+    assert_eq!(labels, ["Version", "Channel", "Host", "Date"]);
+}
+
+#[test]
+fn parse_missing_commit_info() {
+    use rustc_version::{version_meta_for_with_options, ParseOptions};
+
+    let output = "rustc 1.80.0-dev
+host: x86_64-unknown-linux-gnu
+release: 1.80.0-dev";
+
+    match version_meta_for(output) {
+        Err(Error::UnexpectedVersionFormat) => {}
+        _ => panic!("unexpected version format error expected"),
+    }
+
+    let options = ParseOptions::new().allow_missing_commit_info(true);
+    let version = version_meta_for_with_options(output, &options).unwrap();
+    assert_eq!(version.semver, Version::parse("1.80.0-dev").unwrap());
+    assert_eq!(version.host, "x86_64-unknown-linux-gnu");
+    assert_eq!(version.commit_hash, None);
+    assert_eq!(version.commit_date, None);
+}
+
+#[test]
+fn lowest_common_version() {
+    use rustc_version::lowest_common_version;
+
+    assert_eq!(
+        lowest_common_version(&["1.56", "1.60.0", "1.58"]).unwrap(),
+        Version::new(1, 60, 0)
+    );
+    assert_eq!(
+        lowest_common_version(&["1.60", "1.60.1"]).unwrap(),
+        Version::new(1, 60, 1)
+    );
+    assert_eq!(lowest_common_version(&[]).unwrap(), Version::new(1, 0, 0));
+    assert!(lowest_common_version(&["1.56", "latest"]).is_err());
+}
+
+#[test]
+fn llvm_version_u64() {
+    let v: LlvmVersion = "17.0.6".parse().unwrap();
+    assert_eq!(v.to_u64(), Some(17_000_006));
+    assert_eq!(LlvmVersion::from_u64(v.to_u64().unwrap()), v);
+
+    let v: LlvmVersion = "3.9.1".parse().unwrap();
+    assert_eq!(LlvmVersion::from_u64(v.to_u64().unwrap()), v);
+
+    let v: LlvmVersion = "11".parse().unwrap();
+    assert_eq!(v.to_u64(), Some(11_000_000));
+    assert_eq!(
+        LlvmVersion::from_u64(v.to_u64().unwrap()).major_minor_patch(),
+        (11, 0, Some(0))
+    );
+
+    // versions that can't be encoded without colliding or overflowing
+    for &v in &["17.1000", "17.0.1000", "99999999999999"] {
+        let v: LlvmVersion = v.parse().unwrap();
+        assert_eq!(v.to_u64(), None);
+    }
+    let max = LlvmVersion::from_u64(u64::max_value());
+    assert_eq!(max.to_u64(), Some(u64::max_value()));
+    let past_max = LlvmVersion {
+        patch: max.patch.map(|p| p + 1),
+        ..max
+    };
+    assert_eq!(past_max.to_u64(), None);
+
+    let versions: Vec<LlvmVersion> = ["3.9.1", "4.0.0", "17.0.5", "17.0.6", "18.0.0", "18.1.7"]
+        .iter()
+        .map(|v| v.parse().unwrap())
+        .collect();
+    for pair in versions.windows(2) {
+        assert!(pair[0] < pair[1]);
+        assert!(pair[0].to_u64() < pair[1].to_u64());
+    }
+}
+
+#[test]
+fn parallel_frontend() {
+    let output = "rustc 1.80.0-nightly (72fdf913c 2024-06-05)
+binary: rustc
+commit-hash: 72fdf913c53dd0e75313ba83e4aa80df3f6e2871
+commit-date: 2024-06-05
+host: x86_64-unknown-linux-gnu
+release: 1.80.0-nightly
+parallel: yes
+LLVM version: 18.1.6";
+
+    let version = version_meta_for(output).unwrap();
+    assert_eq!(version.parallel_frontend(), Some(true));
+    assert_eq!(version.compiler_debug_assertions(), None);
+    assert_eq!(
+        version_meta_for(&version.to_verbose_string())
+            .unwrap()
+            .parallel_frontend(),
+        Some(true)
+    );
+
+    let version = version_meta_for(&output.replace("parallel: yes", "parallel: no")).unwrap();
+    assert_eq!(version.parallel_frontend(), Some(false));
+
+    let version = version_meta_for(&output.replace("parallel: yes\n", "")).unwrap();
+    assert_eq!(version.parallel_frontend(), None);
+}
+
+#[test]
+fn version_meta_partial() {
+    use rustc_version::version_meta_for_partial;
+
+    let output = "rustc 1.80.0 (051478957 2024-07-21)
+binary: rustc
+commit-hash: 051478957371ee0084a7c0913941d2a8c4757bb9
+commit-date: 2024-07-21
+host: x86_64-unknown-linux-gnu
+release: 1.80.0
+LLVM version: 18.x";
+
+    let (version, errors) = version_meta_for_partial(output);
+    let version = version.unwrap();
+    assert_eq!(version.semver, Version::parse("1.80.0").unwrap());
+    assert_eq!(version.host, "x86_64-unknown-linux-gnu");
+    assert_eq!(version.llvm_version, None);
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].code(), "llvm");
+    assert_eq!(
+        version_meta_for(output).unwrap_err().code(),
+        errors[0].code()
+    );
+
+    let output = output.replace("commit-date: 2024-07-21\n", "");
+    let (version, errors) = version_meta_for_partial(&output);
+    assert_eq!(version.unwrap().commit_date, None);
+    let codes: Vec<_> = errors.iter().map(Error::code).collect();
+    // the same order as the errors of `version_meta_for`
+    assert_eq!(codes, ["format", "llvm"]);
+    assert_eq!(version_meta_for(&output).unwrap_err().code(), "format");
+
+    let nightly = output.replace("release: 1.80.0", "release: 1.80.0-foo");
+    let (_, errors) = version_meta_for_partial(&nightly);
+    let codes: Vec<_> = errors.iter().map(Error::code).collect();
+    assert_eq!(codes, ["pre_release_tag", "format", "llvm"]);
+    assert_eq!(
+        version_meta_for(&nightly).unwrap_err().code(),
+        "pre_release_tag"
+    );
+
+    // nothing is parsed past a missing release
+    let (version, errors) = version_meta_for_partial(&output.replace("release: 1.80.0\n", ""));
+    assert!(version.is_none());
+    let codes: Vec<_> = errors.iter().map(Error::code).collect();
+    assert_eq!(codes, ["format"]);
+
+    let (version, errors) = version_meta_for_partial("rustc 1.80.0\nrelease: 1.80.0\nhost: x\n");
+    assert!(version.is_some());
+    assert_eq!(errors.len(), 2);
+}
+
+#[test]
+fn raw_output() {
+    let output = "rustc 1.47.0 (18bf6b4f0 2020-10-07)
+binary: rustc
+commit-hash: 18bf6b4f01a6feaf7259ba7cdae58031af1b7b39
+commit-date: 2020-10-07
+host: x86_64-unknown-linux-gnu
+release: 1.47.0
+LLVM version: 11.0
+";
+
+    let version = version_meta_for(output).unwrap();
+    assert_eq!(version.raw(), Some(output));
+    assert_eq!(version.without_metadata().raw(), None);
+    let trimmed = version_meta_for(output.trim_end()).unwrap();
+    assert_eq!(trimmed.raw(), Some(output.trim_end()));
+    assert_eq!(trimmed, version);
+
+    let with_warning = format!("warning: ignoring RUSTFLAGS\n{}", output);
+    let version = version_meta_for_lenient(&with_warning).unwrap();
+    assert_eq!(version.raw(), Some(&*with_warning));
+
+    let version = version_meta().unwrap();
+    assert!(version.raw().unwrap().contains("release: "));
+}
+
+#[test]
+fn version_meta_new() {
+    let mut version = VersionMeta::new(
+        Version::new(1, 47, 0),
+        Channel::Stable,
+        "powerpc64le-unknown-linux-gnu",
+        "rustc 1.47.0 (18bf6b4f0 2020-10-07)",
+    );
+    assert_eq!(version.commit_hash, None);
+    assert_eq!(version.llvm_version, None);
+    assert_eq!(version.kind(), CompilerKind::Rustc);
+
+    let parsed = stable_1_47();
+    version.commit_hash = parsed.commit_hash.clone();
+    version.commit_date = parsed.commit_date.clone();
+    version.llvm_version = parsed.llvm_version.clone();
+    assert_eq!(version, parsed);
+    assert_eq!(version.all_llvm_versions(), parsed.all_llvm_versions());
+
+    let cargo = VersionMeta::new(
+        Version::new(1, 47, 0),
+        Channel::Stable,
+        "x86_64-unknown-linux-gnu",
+        "cargo 1.47.0 (f3c7e066a 2020-08-28)",
+    );
+    assert_eq!(cargo.kind(), CompilerKind::Cargo);
+}
+
+#[test]
+fn parse_lenient_leading_noise_other_tools() {
+    let warning = "warning: unused config key `build.foo` in `/home/user/.cargo/config.toml`\n";
+
+    let miri = "miri 0.1.0 (c4c4b8c 2024-04-08)
+rustc 1.79.0-nightly (c4c4b8c0a 2024-04-08)
+binary: rustc
+commit-hash: c4c4b8c0ab3ac42ed67e2d64c4ccd67b21d1c2b1
+commit-date: 2024-04-08
+host: x86_64-unknown-linux-gnu
+release: 1.79.0-nightly
+LLVM version: 18.1.3";
+    let version = version_meta_for_lenient(&format!("{}{}", warning, miri)).unwrap();
+    assert_eq!(version.kind(), CompilerKind::Miri);
+    assert_eq!(version.semver, Version::parse("1.79.0-nightly").unwrap());
+
+    for &(program, kind) in &[
+        ("rustdoc", CompilerKind::Rustdoc),
+        ("cargo", CompilerKind::Cargo),
+    ] {
+        let output = format!(
+            "{}{} 1.80.0 (051478957 2024-07-21)
+binary: {}
+commit-hash: 051478957371ee0084a7c0913941d2a8c4757bb9
+commit-date: 2024-07-21
+host: x86_64-unknown-linux-gnu
+release: 1.80.0",
+            warning, program, program
+        );
+        let version = version_meta_for_lenient(&output).unwrap();
+        assert_eq!(version.kind(), kind);
+        assert_eq!(version.semver, Version::new(1, 80, 0));
+    }
+
+    match version_meta_for_lenient(warning) {
+        Err(Error::NotACompilerVersion(ref line)) => assert!(line.starts_with("warning: ")),
+        _ => panic!("not a compiler version error expected"),
+    }
 }
 
 /*
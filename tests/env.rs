@@ -0,0 +1,48 @@
+//! Tests that modify the process environment.
+//!
+//! These live in their own test binary so that they can't interfere with
+//! tests running `rustc` concurrently in `all.rs`.
+
+use std::env;
+#[cfg(unix)]
+use std::{fs, os::unix::fs::PermissionsExt};
+
+use rustc_version::rustc_bootstrap_enabled;
+#[cfg(unix)]
+use rustc_version::{version_meta, Error};
+
+#[test]
+#[cfg(unix)]
+fn rustc_path_with_spaces() {
+    let dir = env::temp_dir().join("rustc version test");
+    fs::create_dir_all(&dir).unwrap();
+    let rustc = dir.join("rustc wrapper");
+    fs::write(&rustc, "#!/bin/sh\nexec rustc \"$@\"\n").unwrap();
+    fs::set_permissions(&rustc, fs::Permissions::from_mode(0o755)).unwrap();
+
+    env::set_var("RUSTC", &rustc);
+    let res = version_meta();
+    env::set_var("RUSTC", "rustc --some-flag");
+    let with_args = version_meta();
+    env::remove_var("RUSTC");
+
+    assert!(res.unwrap().semver.major >= 1);
+    match with_args {
+        Err(Error::RustcContainsArguments(ref rustc)) => assert_eq!(rustc, "rustc --some-flag"),
+        _ => panic!("RustcContainsArguments error expected"),
+    }
+}
+
+#[test]
+fn bootstrap_enabled() {
+    env::set_var("RUSTC_BOOTSTRAP", "1");
+    assert!(rustc_bootstrap_enabled());
+    env::set_var("RUSTC_BOOTSTRAP", "0");
+    assert!(!rustc_bootstrap_enabled());
+    env::set_var("RUSTC_BOOTSTRAP", "serde_derive,proc_macro2");
+    assert!(rustc_bootstrap_enabled());
+    env::set_var("RUSTC_BOOTSTRAP", "-1");
+    assert!(!rustc_bootstrap_enabled());
+    env::remove_var("RUSTC_BOOTSTRAP");
+    assert!(!rustc_bootstrap_enabled());
+}
@@ -0,0 +1,32 @@
+//! Tests that modify `$RUSTUP_TOOLCHAIN`.
+//!
+//! These live in their own test binary since rustup reads the variable when
+//! running `rustc`, so changing it could break tests running `rustc`
+//! concurrently.
+
+use std::env;
+
+use rustc_version::{active_rustup_toolchain, channel_from_rustup_env, Channel};
+
+#[test]
+fn rustup_toolchain() {
+    env::set_var(
+        "RUSTUP_TOOLCHAIN",
+        "nightly-2023-06-01-x86_64-unknown-linux-gnu",
+    );
+    assert_eq!(
+        active_rustup_toolchain().as_ref().map(|s| &**s),
+        Some("nightly-2023-06-01-x86_64-unknown-linux-gnu")
+    );
+    assert_eq!(channel_from_rustup_env(), Some(Channel::Nightly));
+
+    env::set_var("RUSTUP_TOOLCHAIN", "1.70.0");
+    assert_eq!(channel_from_rustup_env(), Some(Channel::Stable));
+
+    env::set_var("RUSTUP_TOOLCHAIN", "my-custom-build");
+    assert_eq!(channel_from_rustup_env(), None);
+
+    env::remove_var("RUSTUP_TOOLCHAIN");
+    assert_eq!(active_rustup_toolchain(), None);
+    assert_eq!(channel_from_rustup_env(), None);
+}